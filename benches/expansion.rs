@@ -0,0 +1,123 @@
+//! Compares the iterative, double-buffered expansion against the original recursive implementation,
+//! which allocated a fresh `Vec` for every generation.
+//!
+//! Run with `cargo bench --bench expansion`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lsystem::context::complex_lsystem;
+use lsystem::deterministic::lsystem;
+
+/// The original implementation of `lsystem`, kept as a baseline.
+fn recursive_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(T, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    if iterations == 0 {
+        return axiom;
+    }
+    let mut result = Vec::with_capacity(axiom.len() * 2);
+    'elements: for elem in axiom {
+        for (original, replacement) in rules {
+            if elem == *original {
+                result.append(&mut replacement.clone());
+                continue 'elements;
+            }
+        }
+        result.push(elem);
+    }
+    if iterations > 1 {
+        recursive_lsystem(result, rules, iterations - 1)
+    } else {
+        result
+    }
+}
+
+/// The original implementation of `complex_lsystem`, kept as a baseline.
+fn recursive_complex_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    if iterations == 0 {
+        return axiom;
+    }
+    let mut result: Vec<T> = Vec::with_capacity(axiom.len() * 2);
+    let mut i = 0;
+    'outer: while i < axiom.len() {
+        'inner: for (original, replacement) in rules {
+            if i + original.len() > axiom.len() {
+                continue 'inner;
+            }
+            if axiom[i..i + original.len()] == *original.as_slice() {
+                result.append(&mut replacement.clone());
+                i += original.len();
+                continue 'outer;
+            }
+        }
+        result.push(axiom[i].clone());
+        i += 1;
+    }
+    if iterations > 1 {
+        recursive_complex_lsystem(result, rules, iterations - 1)
+    } else {
+        result
+    }
+}
+
+/// Runs `f` a few times and reports the fastest run.
+fn bench(name: &str, mut f: impl FnMut() -> usize) -> Duration {
+    let mut best = Duration::MAX;
+    let mut len = 0;
+    for _ in 0..5 {
+        let start = Instant::now();
+        len = black_box(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<40} {best:>12.3?}  ({len} elements)");
+    best
+}
+
+fn compare(name: &str, baseline: impl FnMut() -> usize, iterative: impl FnMut() -> usize) {
+    let baseline = bench(&format!("{name} (recursive)"), baseline);
+    let iterative = bench(&format!("{name} (iterative)"), iterative);
+    println!(
+        "{:<40} {:>11.2}x\n",
+        "speedup",
+        baseline.as_secs_f64() / iterative.as_secs_f64()
+    );
+}
+
+fn main() {
+    let fibonacci = vec![('A', vec!['A', 'B']), ('B', vec!['A'])];
+    compare(
+        "fibonacci, 30 generations",
+        || recursive_lsystem(vec!['A'], &fibonacci, 30).len(),
+        || lsystem(vec!['A'], &fibonacci, 30).len(),
+    );
+
+    let koch: Vec<char> = "F+F-F-F+F".chars().collect();
+    let koch_rules = vec![('F', koch.clone())];
+    compare(
+        "koch, 8 generations",
+        || recursive_lsystem(vec!['F'], &koch_rules, 8).len(),
+        || lsystem(vec!['F'], &koch_rules, 8).len(),
+    );
+
+    let complex_rules = vec![(vec!['F'], koch)];
+    compare(
+        "complex koch, 8 generations",
+        || recursive_complex_lsystem(vec!['F'], &complex_rules, 8).len(),
+        || complex_lsystem(vec!['F'], &complex_rules, 8).len(),
+    );
+
+    // many generations of a constant-sized string, where the recursion depth dominates
+    let rotate = vec![('A', vec!['B']), ('B', vec!['C']), ('C', vec!['A'])];
+    compare(
+        "rotation, 20000 generations",
+        || recursive_lsystem(vec!['A', 'B', 'C'], &rotate, 20_000).len(),
+        || lsystem(vec!['A', 'B', 'C'], &rotate, 20_000).len(),
+    );
+}
@@ -0,0 +1,73 @@
+//! Compares the linear scan over all rules against the hash-indexed lookups on grammars with
+//! large alphabets.
+//!
+//! Run with `cargo bench --bench indexed`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lsystem::context::{complex_lsystem, indexed_complex_lsystem};
+use lsystem::deterministic::{indexed_lsystem, lsystem};
+
+/// Runs `f` a few times and reports the fastest run.
+fn bench(name: &str, mut f: impl FnMut() -> usize) -> Duration {
+    let mut best = Duration::MAX;
+    let mut len = 0;
+    for _ in 0..3 {
+        let start = Instant::now();
+        len = black_box(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<45} {best:>12.3?}  ({len} elements)");
+    best
+}
+
+fn compare(name: &str, linear: impl FnMut() -> usize, indexed: impl FnMut() -> usize) {
+    let linear = bench(&format!("{name} (linear)"), linear);
+    let indexed = bench(&format!("{name} (indexed)"), indexed);
+    println!(
+        "{:<45} {:>11.2}x\n",
+        "speedup",
+        linear.as_secs_f64() / indexed.as_secs_f64()
+    );
+}
+
+/// Every symbol grows into two symbols of the alphabet, except for a quarter that are constants.
+fn symbol_rules(alphabet: u32) -> Vec<(u32, Vec<u32>)> {
+    (0..alphabet)
+        .filter(|s| s % 4 != 0)
+        .map(|s| (s, vec![(s * 7 + 1) % alphabet, (s * 13 + 5) % alphabet]))
+        .collect()
+}
+
+/// Patterns of one to three symbols, rewritten into two to four symbols.
+fn slice_rules(alphabet: u32) -> Vec<(Vec<u32>, Vec<u32>)> {
+    (0..alphabet)
+        .map(|s| {
+            let original = (0..=s % 3).map(|i| (s + i * 17) % alphabet).collect();
+            let replacement = (0..=s % 3 + 1).map(|i| (s * 11 + i) % alphabet).collect();
+            (original, replacement)
+        })
+        .collect()
+}
+
+fn main() {
+    for alphabet in [16, 128, 512] {
+        let axiom: Vec<u32> = (0..alphabet).collect();
+        let rules = symbol_rules(alphabet);
+        compare(
+            &format!("{alphabet} symbols, 8 generations"),
+            || lsystem(axiom.clone(), &rules, 8).len(),
+            || indexed_lsystem(axiom.clone(), &rules, 8).len(),
+        );
+    }
+    for alphabet in [16, 128, 512] {
+        let axiom: Vec<u32> = (0..alphabet).collect();
+        let rules = slice_rules(alphabet);
+        compare(
+            &format!("{alphabet} slice rules, 6 generations"),
+            || complex_lsystem(axiom.clone(), &rules, 6).len(),
+            || indexed_complex_lsystem(axiom.clone(), &rules, 6).len(),
+        );
+    }
+}
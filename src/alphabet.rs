@@ -0,0 +1,67 @@
+//! Dense numbering of the symbols of a context-free, deterministic L-System.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The alphabet of an L-System given as `(T, Vec<T>)` rules, with every symbol numbered in order
+/// of first appearance and every production translated to those numbers.
+///
+/// As in [`lsystem`](crate::deterministic::lsystem), only the first rule for a symbol applies, and
+/// symbols without a rule are constants, i.e. they produce themselves.
+pub(crate) struct Alphabet<T> {
+    pub(crate) symbols: Vec<T>,
+    pub(crate) productions: Vec<Vec<usize>>,
+    index: HashMap<T, usize>,
+}
+
+impl<T: Hash + Eq + Clone> Alphabet<T> {
+    pub(crate) fn new(axiom: &[T], rules: &[(T, Vec<T>)]) -> Self {
+        let mut alphabet = Alphabet {
+            symbols: Vec::new(),
+            productions: Vec::new(),
+            index: HashMap::new(),
+        };
+        let occurrences = axiom.iter().chain(
+            rules
+                .iter()
+                .flat_map(|(original, replacement)| std::iter::once(original).chain(replacement)),
+        );
+        for symbol in occurrences {
+            alphabet.insert(symbol);
+        }
+        let mut productions: Vec<Option<Vec<usize>>> = vec![None; alphabet.symbols.len()];
+        for (original, replacement) in rules {
+            let production = &mut productions[alphabet.index[original]];
+            if production.is_none() {
+                *production = Some(replacement.iter().map(|s| alphabet.index[s]).collect());
+            }
+        }
+        alphabet.productions = productions
+            .into_iter()
+            .enumerate()
+            .map(|(symbol, production)| production.unwrap_or_else(|| vec![symbol]))
+            .collect();
+        alphabet
+    }
+
+    fn insert(&mut self, symbol: &T) {
+        if !self.index.contains_key(symbol) {
+            self.index.insert(symbol.clone(), self.symbols.len());
+            self.symbols.push(symbol.clone());
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// The number of `symbol`, if it is part of the alphabet.
+    pub(crate) fn index_of(&self, symbol: &T) -> Option<usize> {
+        self.index.get(symbol).copied()
+    }
+
+    /// Translates a string of symbols that are all part of the alphabet to their numbers.
+    pub(crate) fn encode(&self, symbols: &[T]) -> Vec<usize> {
+        symbols.iter().map(|s| self.index[s]).collect()
+    }
+}
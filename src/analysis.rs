@@ -0,0 +1,176 @@
+//! Predictions about the growth of context-free, deterministic L-Systems.
+
+use std::hash::Hash;
+
+use crate::alphabet::Alphabet;
+
+/// The growth matrix of an L-System with `(T, Vec<T>)` rules, as expanded by
+/// [`lsystem`](crate::deterministic::lsystem).
+///
+/// Row `i` of the matrix counts how often each symbol occurs in the production of symbol `i`, so
+/// the Parikh vector of a generation (how often each symbol occurs in it) is the Parikh vector of
+/// the axiom multiplied by the `n`-th power of the matrix. Counts are `u128` and saturate at
+/// `u128::MAX`.
+///
+/// ```
+/// use lsystem::analysis::Growth;
+///
+/// let fibonacci = Growth::new(&['A'], &[('A', vec!['A', 'B']), ('B', vec!['A'])]);
+/// assert_eq!(fibonacci.len(10), 144);
+/// assert_eq!(fibonacci.parikh(10), vec![89, 55]);
+/// assert!((fibonacci.growth_rate() - 1.618034).abs() < 1e-6);
+/// ```
+pub struct Growth<T> {
+    alphabet: Alphabet<T>,
+    axiom: Vec<u128>,
+    matrix: Vec<Vec<u128>>,
+}
+
+impl<T: Hash + Eq + Clone> Growth<T> {
+    pub fn new(axiom: &[T], rules: &[(T, Vec<T>)]) -> Self {
+        let alphabet = Alphabet::new(axiom, rules);
+        let size = alphabet.len();
+        let mut parikh = vec![0; size];
+        for symbol in alphabet.encode(axiom) {
+            parikh[symbol] += 1;
+        }
+        let matrix = alphabet
+            .productions
+            .iter()
+            .map(|production| {
+                let mut row = vec![0; size];
+                for &symbol in production {
+                    row[symbol] += 1;
+                }
+                row
+            })
+            .collect();
+        Growth {
+            alphabet,
+            axiom: parikh,
+            matrix,
+        }
+    }
+
+    /// All symbols of the L-System, in the order used by [`Growth::parikh`] and [`Growth::matrix`].
+    pub fn symbols(&self) -> &[T] {
+        &self.alphabet.symbols
+    }
+
+    /// The growth matrix, `matrix()[i][j]` is how often symbol `j` occurs in the production of
+    /// symbol `i`.
+    pub fn matrix(&self) -> &[Vec<u128>] {
+        &self.matrix
+    }
+
+    /// How often each of the [`symbols`](Growth::symbols) occurs in the given generation.
+    pub fn parikh(&self, generation: u32) -> Vec<u128> {
+        let mut parikh = self.axiom.clone();
+        let mut power = self.matrix.clone();
+        let mut n = generation;
+        while n > 0 {
+            if n & 1 == 1 {
+                parikh = multiply(&[parikh], &power).pop().unwrap();
+            }
+            n >>= 1;
+            if n > 0 {
+                power = multiply(&power, &power);
+            }
+        }
+        parikh
+    }
+
+    /// How often `symbol` occurs in the given generation.
+    pub fn count(&self, generation: u32, symbol: &T) -> u128 {
+        match self.alphabet.index_of(symbol) {
+            Some(index) => self.parikh(generation)[index],
+            None => 0,
+        }
+    }
+
+    /// The number of elements of the given generation.
+    pub fn len(&self, generation: u32) -> u128 {
+        self.parikh(generation)
+            .into_iter()
+            .fold(0, u128::saturating_add)
+    }
+
+    /// Whether the given generation has no elements at all.
+    pub fn is_empty(&self, generation: u32) -> bool {
+        self.len(generation) == 0
+    }
+
+    /// The factor by which the length of the generations grows in the long run, i.e. the dominant
+    /// eigenvalue of the growth matrix restricted to the symbols that can be derived from the axiom.
+    ///
+    /// This is a numerical estimate using power iteration, which converges slowly for systems
+    /// that grow polynomially rather than exponentially.
+    pub fn growth_rate(&self) -> f64 {
+        const MAX_ITERATIONS: usize = 100_000;
+        const TOLERANCE: f64 = 1e-13;
+
+        // if nothing survives as many generations as there are symbols, nothing survives at all
+        if self.is_empty(self.alphabet.len() as u32) {
+            return 0.0;
+        }
+        let reachable = self.reachable();
+        // iterating with the matrix shifted by the identity avoids oscillating between
+        // eigenvalues of the same magnitude, and keeps every reachable symbol represented
+        let mut vector: Vec<f64> = reachable
+            .iter()
+            .map(|&r| if r { 1.0 } else { 0.0 })
+            .collect();
+        let mut rate = f64::NAN;
+        for _ in 0..MAX_ITERATIONS {
+            let total: f64 = vector.iter().sum();
+            let mut next = vector.clone();
+            for (i, row) in self.matrix.iter().enumerate() {
+                for (j, &count) in row.iter().enumerate() {
+                    next[j] += vector[i] * count as f64;
+                }
+            }
+            let next_rate = next.iter().sum::<f64>() / total - 1.0;
+            let converged = (next_rate - rate).abs() <= TOLERANCE * next_rate.max(1.0);
+            rate = next_rate;
+            if converged {
+                break;
+            }
+            let next_total: f64 = next.iter().sum();
+            vector = next.into_iter().map(|x| x / next_total).collect();
+        }
+        rate
+    }
+
+    /// Which symbols occur in at least one generation.
+    fn reachable(&self) -> Vec<bool> {
+        let mut reachable: Vec<bool> = self.axiom.iter().map(|&count| count > 0).collect();
+        let mut todo: Vec<usize> = (0..reachable.len()).filter(|&s| reachable[s]).collect();
+        while let Some(symbol) = todo.pop() {
+            for &next in &self.alphabet.productions[symbol] {
+                if !reachable[next] {
+                    reachable[next] = true;
+                    todo.push(next);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+/// Multiplies two matrices with saturating arithmetic.
+fn multiply(a: &[Vec<u128>], b: &[Vec<u128>]) -> Vec<Vec<u128>> {
+    // without symbols there are no rows to take the number of columns from
+    let columns = b.first().map_or(0, Vec::len);
+    a.iter()
+        .map(|row| {
+            (0..columns)
+                .map(|j| {
+                    row.iter()
+                        .zip(b)
+                        .map(|(&x, b_row)| x.saturating_mul(b_row[j]))
+                        .fold(0, u128::saturating_add)
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,169 @@
+//! An Aho-Corasick automaton rewriting a whole generation with slice rules in a single pass.
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::mem;
+use std::ops::Range;
+
+use rand::RngCore;
+
+use crate::rule::{self, Rule};
+use crate::trie::Trie;
+
+/// The rules of a [`complex_lsystem`](crate::context::complex_lsystem), compiled into an
+/// Aho-Corasick automaton over their patterns.
+///
+/// Instead of trying every pattern at every position, the automaton reads each element of a
+/// generation exactly once and reports all patterns ending there. Since no pattern is longer than
+/// the longest one, the rule applying at a position is known for certain once the automaton has
+/// read that far past it, so rewriting happens in the same pass and with the same semantics: any
+/// slice has at most one rule applied to it, and of the rules matching at a position the first
+/// one wins. Rules with an empty pattern never match.
+///
+/// ```
+/// use lsystem::automaton::Automaton;
+///
+/// let automaton = Automaton::new(vec![(vec!['A', 'B'], vec!['C']), (vec!['A'], vec!['A', 'B'])]);
+/// assert_eq!(automaton.expand(vec!['A', 'B', 'A'], 2), vec!['C', 'C']);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Automaton<T> {
+    trie: Trie<T>,
+    /// For each node of the trie, the node of the longest proper suffix of its path that is also
+    /// a path in the trie.
+    fail: Vec<usize>,
+    /// For each node of the trie, the nearest node along the fail links that has a rule.
+    output: Vec<Option<usize>>,
+}
+
+/// Marks a position at which no rule matches.
+const NO_RULE: usize = usize::MAX;
+
+impl<T: Hash + Eq + Clone> Automaton<T> {
+    pub fn new(rules: impl IntoIterator<Item = (Vec<T>, Vec<T>)>) -> Self {
+        let trie = Trie::new(rules);
+        let nodes = trie.nodes.len();
+        let mut automaton = Automaton {
+            trie,
+            fail: vec![0; nodes],
+            output: vec![None; nodes],
+        };
+        automaton.link();
+        automaton
+    }
+
+    /// Computes the fail and output links breadth first, so the links of shorter paths are
+    /// known before they are needed.
+    fn link(&mut self) {
+        let mut queue: VecDeque<usize> = self.trie.nodes[0].children.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            for (elem, &child) in &self.trie.nodes[node].children {
+                let fail = self.step(self.fail[node], elem);
+                self.fail[child] = fail;
+                self.output[child] = match self.trie.nodes[fail].rule {
+                    Some(_) => Some(fail),
+                    None => self.output[fail],
+                };
+                queue.push_back(child);
+            }
+        }
+    }
+
+    /// The state after reading `elem` in `state`.
+    fn step(&self, mut state: usize, elem: &T) -> usize {
+        loop {
+            if let Some(&next) = self.trie.nodes[state].children.get(elem) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    /// Rewrites `input` once, appending the next generation to `out`.
+    pub fn rewrite(&self, input: &[T], out: &mut Vec<T>) {
+        // without patterns nothing is ever replaced, but the buffer of pending matches needs a slot
+        let depth = self.trie.depth.max(1);
+        // the first rule matching at each of the last `depth` positions, indexed modulo `depth`
+        let mut first = vec![NO_RULE; depth];
+        // the first position that is not part of a replaced slice yet
+        let mut cursor = 0;
+        let mut settle = |start: usize, first: &mut [usize]| {
+            let rule = mem::replace(&mut first[start % depth], NO_RULE);
+            if start < cursor {
+                return;
+            }
+            if rule == NO_RULE {
+                out.push(input[start].clone());
+                cursor = start + 1;
+            } else {
+                out.extend_from_slice(&self.trie.replacements[rule]);
+                cursor = start + self.trie.patterns[rule];
+            }
+        };
+
+        let mut state = 0;
+        for (end, elem) in input.iter().enumerate() {
+            state = self.step(state, elem);
+            let mut node = match self.trie.nodes[state].rule {
+                Some(_) => Some(state),
+                None => self.output[state],
+            };
+            while let Some(matched) = node {
+                let start = end + 1 - self.trie.nodes[matched].depth;
+                let slot = &mut first[start % depth];
+                *slot = (*slot).min(self.trie.nodes[matched].rule.unwrap());
+                node = self.output[matched];
+            }
+            // no pattern that starts at `end + 1 - depth` can still be found
+            if let Some(start) = (end + 1).checked_sub(depth) {
+                settle(start, &mut first);
+            }
+        }
+        for start in input.len().saturating_sub(depth - 1)..input.len() {
+            settle(start, &mut first);
+        }
+    }
+
+    /// Rewrites `axiom` the given number of times, each generation in a single pass.
+    pub fn expand(&self, axiom: Vec<T>, iterations: u32) -> Vec<T> {
+        rule::iterate(axiom, iterations, |input, _, out| self.rewrite(input, out))
+    }
+}
+
+/// As a rule, for use in an [`LSystem`](crate::LSystem) or with its limits, the automaton finds
+/// the rule for each position on its own by walking down the trie of patterns, like a
+/// [`PatternTrie`](crate::context::PatternTrie). The single pass over a whole generation is only
+/// taken by [`Automaton::rewrite`] and [`Automaton::expand`].
+impl<T: Hash + Eq + Clone> Rule<T> for Automaton<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        self.trie.find(input, at).map(|(_, len)| len)
+    }
+
+    fn produce(&self, input: &[T], matched: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        self.trie.produce(&input[matched], out);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(self.trie.depth)
+    }
+}
+
+/// Like [`complex_lsystem`](crate::context::complex_lsystem), but rewrites each generation in a
+/// single pass of an [`Automaton`] built from the rules.
+///
+/// ```
+/// use lsystem::automaton::automaton_lsystem;
+///
+/// let rules = vec![(vec!['A', 'B'], vec!['C']), (vec!['A'], vec!['A', 'B'])];
+/// assert_eq!(automaton_lsystem(vec!['A', 'B', 'A'], &rules, 1), vec!['C', 'A', 'B']);
+/// ```
+pub fn automaton_lsystem<T: Hash + Eq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    Automaton::new(rules.iter().cloned()).expand(axiom, iterations)
+}
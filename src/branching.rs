@@ -0,0 +1,184 @@
+//! Context matching that follows the branch structure of bracketed L-Systems.
+
+/// Describes how the elements of a generation form a tree of branches, and which elements are
+/// invisible to context-sensitive rules.
+///
+/// A branch starts with the `push` symbol and ends with the matching `pop` symbol. Context is
+/// matched along the tree instead of the string: the left context of an element is the path
+/// leading to it from the root, and its right context continues into the branches that grow out
+/// of it, skipping the branches that the context does not mention. Ignored symbols, typically the
+/// geometric ones like `+` and `-`, are skipped as if they were not there.
+///
+/// ```
+/// use lsystem::branching::Branching;
+/// use lsystem::context::ContextRule;
+/// use lsystem::LSystem;
+///
+/// // in A[+B]C the parent of both B and C is A
+/// let system = LSystem::new("A[+B]C".chars().collect())
+///     .with_rule(
+///         ContextRule::new(vec!['A'], vec!['C'], vec![], vec!['X'])
+///             .with_branching(Branching::brackets().ignoring(['+', '-'])),
+///     )
+///     .with_rule(
+///         ContextRule::new(vec!['A'], vec!['B'], vec![], vec!['Y'])
+///             .with_branching(Branching::brackets().ignoring(['+', '-'])),
+///     );
+/// assert_eq!(system.expand(1), "A[+Y]X".chars().collect::<Vec<_>>());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branching<S> {
+    pub push: S,
+    pub pop: S,
+    pub ignore: Vec<S>,
+}
+
+impl<S> Branching<S> {
+    pub fn new(push: S, pop: S) -> Self {
+        Branching {
+            push,
+            pop,
+            ignore: Vec::new(),
+        }
+    }
+
+    /// Adds symbols that are skipped when matching context.
+    pub fn ignoring(mut self, ignore: impl IntoIterator<Item = S>) -> Self {
+        self.ignore.extend(ignore);
+        self
+    }
+}
+
+impl Branching<char> {
+    /// Branches delimited by `[` and `]`, without ignored symbols.
+    pub fn brackets() -> Self {
+        Branching::new('[', ']')
+    }
+}
+
+impl<S: PartialEq> Branching<S> {
+    fn is_ignored(&self, symbol: &S) -> bool {
+        self.ignore.contains(symbol)
+    }
+
+    /// Whether `context` matches the path leading to `input[at]` from the root.
+    ///
+    /// `symbol` extracts the symbol of an element, and `matches` compares an element of the
+    /// context with an element of the input.
+    pub(crate) fn left_context_matches<'c, T, P>(
+        &self,
+        input: &[T],
+        at: usize,
+        context: &'c [P],
+        symbol: impl Fn(&T) -> &S,
+        mut matches: impl FnMut(&'c P, &T) -> bool,
+    ) -> bool {
+        let mut i = at;
+        for expected in context.iter().rev() {
+            loop {
+                if i == 0 {
+                    return false;
+                }
+                i -= 1;
+                let s = symbol(&input[i]);
+                if *s == self.pop {
+                    // a complete sibling branch, which is not part of the path
+                    let mut depth = 1;
+                    while depth > 0 {
+                        if i == 0 {
+                            return false;
+                        }
+                        i -= 1;
+                        let s = symbol(&input[i]);
+                        if *s == self.pop {
+                            depth += 1;
+                        } else if *s == self.push {
+                            depth -= 1;
+                        }
+                    }
+                } else if *s != self.push && !self.is_ignored(s) {
+                    break;
+                }
+            }
+            if !matches(expected, &input[i]) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether `context` matches the elements following `input[..end]` along the branch
+    /// structure. Branches in the input that the context does not mention are skipped, a `push`
+    /// in the context descends into a branch, and a `pop` in the context skips the rest of the
+    /// branch the match is in.
+    ///
+    /// `input_symbol` and `context_symbol` extract the symbols of elements of the input and the
+    /// context, and `matches` compares an element of the context with an element of the input.
+    pub(crate) fn right_context_matches<'c, T, P>(
+        &self,
+        input: &[T],
+        end: usize,
+        context: &'c [P],
+        input_symbol: impl Fn(&T) -> &S,
+        context_symbol: impl Fn(&P) -> &S,
+        mut matches: impl FnMut(&'c P, &T) -> bool,
+    ) -> bool {
+        // the index after the `pop` closing the branch that `input[i]` is in
+        let skip_branch = |mut i: usize| -> Option<usize> {
+            let mut depth = 0;
+            while i < input.len() {
+                let s = input_symbol(&input[i]);
+                if *s == self.push {
+                    depth += 1;
+                } else if *s == self.pop {
+                    if depth == 0 {
+                        return Some(i + 1);
+                    }
+                    depth -= 1;
+                }
+                i += 1;
+            }
+            None
+        };
+
+        let mut i = end;
+        for expected in context {
+            let expected_symbol = context_symbol(expected);
+            if *expected_symbol == self.pop {
+                match skip_branch(i) {
+                    Some(next) => i = next,
+                    None => return false,
+                }
+                continue;
+            }
+            loop {
+                let Some(elem) = input.get(i) else {
+                    return false;
+                };
+                let s = input_symbol(elem);
+                if self.is_ignored(s) {
+                    i += 1;
+                } else if *s == self.push && *expected_symbol != self.push {
+                    match skip_branch(i + 1) {
+                        Some(next) => i = next,
+                        None => return false,
+                    }
+                } else if *s == self.pop {
+                    // the branch ends before the context does
+                    return false;
+                } else {
+                    break;
+                }
+            }
+            if *expected_symbol == self.push {
+                if *input_symbol(&input[i]) != self.push {
+                    return false;
+                }
+            } else if !matches(expected, &input[i]) {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
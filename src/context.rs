@@ -0,0 +1,212 @@
+//! Deterministic L-Systems whose rules match slices of elements instead of single elements,
+//! including context-sensitive ones.
+
+use std::hash::Hash;
+use std::ops::Range;
+
+use rand::RngCore;
+
+use crate::branching::Branching;
+use crate::rule::{self, Rule};
+use crate::trie::Trie;
+
+/// A rule replacing a slice of elements by a sequence of elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceRule<T> {
+    pub original: Vec<T>,
+    pub replacement: Vec<T>,
+}
+
+impl<T> SliceRule<T> {
+    pub fn new(original: Vec<T>, replacement: Vec<T>) -> Self {
+        SliceRule {
+            original,
+            replacement,
+        }
+    }
+}
+
+impl<T> From<(Vec<T>, Vec<T>)> for SliceRule<T> {
+    fn from((original, replacement): (Vec<T>, Vec<T>)) -> Self {
+        SliceRule::new(original, replacement)
+    }
+}
+
+impl<T: PartialEq + Clone> Rule<T> for SliceRule<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        input[at..]
+            .starts_with(&self.original)
+            .then_some(self.original.len())
+    }
+
+    fn produce(&self, _: &[T], _: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.extend_from_slice(&self.replacement);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(self.original.len())
+    }
+}
+
+/// A context-sensitive rule, written `left < original > right -> replacement` in The Algorithmic
+/// Beauty of Plants: `original` is only replaced when it is preceded by `left` and followed by
+/// `right`. The context is matched but not rewritten, so it stays available to other rules, and
+/// like all rules it is matched against the previous generation, so all positions of a generation
+/// are rewritten simultaneously.
+///
+/// ```
+/// use lsystem::context::ContextRule;
+/// use lsystem::deterministic::SymbolRule;
+/// use lsystem::LSystem;
+///
+/// // a signal travelling along a filament: b < a -> b, b -> a
+/// let system = LSystem::new(vec!['b', 'a', 'a', 'a'])
+///     .with_rule(ContextRule::new(vec!['b'], vec!['a'], vec![], vec!['b']))
+///     .with_rule(SymbolRule::new('b', vec!['a']));
+/// assert_eq!(system.expand(2), vec!['a', 'a', 'b', 'a']);
+/// ```
+///
+/// By default the context has to be adjacent in the generation; with
+/// [`ContextRule::with_branching`] it is matched along the branch structure instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextRule<T> {
+    pub left: Vec<T>,
+    pub original: Vec<T>,
+    pub right: Vec<T>,
+    pub replacement: Vec<T>,
+    pub branching: Option<Branching<T>>,
+}
+
+impl<T> ContextRule<T> {
+    pub fn new(left: Vec<T>, original: Vec<T>, right: Vec<T>, replacement: Vec<T>) -> Self {
+        ContextRule {
+            left,
+            original,
+            right,
+            replacement,
+            branching: None,
+        }
+    }
+
+    /// Matches the context along the branches described by `branching`, see [`Branching`].
+    pub fn with_branching(mut self, branching: Branching<T>) -> Self {
+        self.branching = Some(branching);
+        self
+    }
+}
+
+impl<T: PartialEq + Clone> Rule<T> for ContextRule<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        if !input[at..].starts_with(&self.original) {
+            return None;
+        }
+        let end = at + self.original.len();
+        let context_matches = match &self.branching {
+            None => input[..at].ends_with(&self.left) && input[end..].starts_with(&self.right),
+            Some(branching) => {
+                branching.left_context_matches(input, at, &self.left, |t| t, T::eq)
+                    && branching.right_context_matches(input, end, &self.right, |t| t, |t| t, T::eq)
+            }
+        };
+        context_matches.then_some(self.original.len())
+    }
+
+    fn produce(&self, _: &[T], _: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.extend_from_slice(&self.replacement);
+    }
+
+    fn span(&self) -> Option<usize> {
+        match self.branching {
+            None => Some(self.original.len() + self.right.len()),
+            // branches can be arbitrarily long
+            Some(_) => None,
+        }
+    }
+
+    fn lookbehind(&self) -> usize {
+        self.left.len()
+    }
+}
+
+/// a refined implementation of a deterministic L-System, that takes as its rules pairs of `Vec<T>`, the first one will be replaced by the second
+/// any slice of the axiom will have at most one rule applied to it per iteration, and the first one matching wins
+/// this can be used to implement context-aware L-systems
+///
+/// ```
+/// use lsystem::context::complex_lsystem;
+///
+/// let rules = vec![(vec!['A', 'B'], vec!['C']), (vec!['A'], vec!['A', 'B'])];
+/// assert_eq!(complex_lsystem(vec!['A', 'B', 'A'], &rules, 1), vec!['C', 'A', 'B']);
+/// ```
+pub fn complex_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules: Vec<SliceRule<T>> = rules.iter().cloned().map(SliceRule::from).collect();
+    rule::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
+
+/// Like [`complex_lsystem`], but rewrites large generations on several threads.
+/// The output is identical to that of [`complex_lsystem`], also for patterns that straddle the
+/// boundaries between the chunks processed by different threads.
+#[cfg(feature = "parallel")]
+pub fn par_complex_lsystem<T: PartialEq + Clone + Send + Sync>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules: Vec<SliceRule<T>> = rules.iter().cloned().map(SliceRule::from).collect();
+    crate::parallel::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
+
+/// A set of [`SliceRule`]s stored in a trie of their patterns, so finding the rule for a position
+/// only walks along the elements at that position instead of comparing every pattern.
+///
+/// The semantics are those of [`complex_lsystem`]: when several patterns match at a position, the
+/// rule that came first wins, regardless of the length of the patterns.
+#[derive(Debug, Clone)]
+pub struct PatternTrie<T> {
+    trie: Trie<T>,
+}
+
+impl<T: Hash + Eq + Clone> PatternTrie<T> {
+    /// Rules with an empty pattern never match, as in [`Rule::matches`].
+    pub fn new(rules: impl IntoIterator<Item = (Vec<T>, Vec<T>)>) -> Self {
+        PatternTrie {
+            trie: Trie::new(rules),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Rule<T> for PatternTrie<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        self.trie.find(input, at).map(|(_, len)| len)
+    }
+
+    fn produce(&self, input: &[T], matched: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        self.trie.produce(&input[matched], out);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(self.trie.depth)
+    }
+}
+
+/// Like [`complex_lsystem`], but looks up the rule for each position in a [`PatternTrie`], which
+/// is much faster for large sets of rules.
+///
+/// ```
+/// use lsystem::context::indexed_complex_lsystem;
+///
+/// let rules = vec![(vec!['A', 'B'], vec!['C']), (vec!['A'], vec!['A', 'B'])];
+/// assert_eq!(indexed_complex_lsystem(vec!['A', 'B', 'A'], &rules, 1), vec!['C', 'A', 'B']);
+/// ```
+pub fn indexed_complex_lsystem<T: Hash + Eq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules = [PatternTrie::new(rules.iter().cloned())];
+    rule::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
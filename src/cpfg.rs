@@ -0,0 +1,116 @@
+//! Import of grammars in the `.l` format of cpfg and L-studio, in which many of the models of The
+//! Algorithmic Beauty of Plants are published.
+
+use crate::grammar::{Grammar, GrammarError, GrammarParser, Line, Span};
+
+/// Imports the L-System defined in a cpfg `.l` file.
+///
+/// The supported subset covers most published models:
+///
+/// - `/* comments */`, also spanning several lines
+/// - `#define NAME value`, for numeric values, which become constants
+/// - `Lsystem:`, whose label is ignored, `derivation length:`, `Axiom:`, `ignore:` and
+///   `endlsystem`, after which the rest of the file is ignored
+/// - productions `left < predecessor > right : condition --> successor : probability`, where
+///   `*` stands for a missing context or condition
+///
+/// Contexts, conditions, parameters and probabilities mean the same as in a [`Grammar`], so
+/// consecutive productions with the same left-hand side and a probability form a stochastic rule.
+/// Other directives, like `homomorphism` or `consider:`, are reported as errors rather than
+/// silently changing the meaning of the model.
+///
+/// ```
+/// use lsystem::cpfg;
+///
+/// let grammar = cpfg::import(
+///     "
+///     /* The Algorithmic Beauty of Plants, figure 1.24 (a) */
+///     #define delta 25.7
+///     Lsystem: 1
+///     derivation length: 5
+///     Axiom: F
+///     F --> F[+F]F[-F]F
+///     endlsystem
+///     ",
+/// )
+/// .unwrap();
+/// assert_eq!(grammar.iterations, Some(5));
+/// assert_eq!(grammar.constants["delta"], 25.7);
+/// let forward_steps = grammar.into_lsystem().expand(5).iter().filter(|m| m.symbol == 'F').count();
+/// assert_eq!(forward_steps, 5usize.pow(5));
+/// ```
+pub fn import(source: &str) -> Result<Grammar, GrammarError> {
+    let source = strip_comments(source);
+    let mut parser = GrammarParser::new("-->", true);
+    for (index, text) in source.lines().enumerate() {
+        let line = Line {
+            number: index + 1,
+            text,
+        };
+        let content = Span { start: 0, text }.trim();
+        if content.text.is_empty() {
+            continue;
+        }
+        if content.text == "endlsystem" {
+            break;
+        }
+        if let Some(rest) = content.strip_prefix("#define") {
+            let rest = rest.trim();
+            let name_len = rest
+                .text
+                .find(char::is_whitespace)
+                .unwrap_or(rest.text.len());
+            let name = Span {
+                start: rest.start,
+                text: &rest.text[..name_len],
+            };
+            if name.text.contains('(') {
+                return Err(line.error(name.start, "macros with parameters are not supported"));
+            }
+            let value = Span {
+                start: rest.start + name_len,
+                text: &rest.text[name_len..],
+            }
+            .trim();
+            parser.define(&line, name, value)?;
+        } else if content.text.starts_with('#') {
+            return Err(line.error(content.start, "unsupported preprocessor directive"));
+        } else if content.strip_prefix("Lsystem:").is_some() {
+            // the label only matters to files with several L-Systems
+        } else if let Some(rest) = content.strip_prefix("derivation length:") {
+            parser.iterations(&line, rest.trim())?;
+        } else if let Some(rest) = content.strip_prefix("Axiom:") {
+            parser.axiom(&line, content.start, rest)?;
+        } else if let Some(rest) = content.strip_prefix("ignore:") {
+            parser.ignore(rest);
+        } else if content.split_first("-->").is_some() {
+            parser.rule(&line, content)?;
+        } else {
+            let directive = content.text.split(':').next().unwrap_or(content.text);
+            return Err(line.error(
+                content.start,
+                format!("`{}` is not supported", directive.trim()),
+            ));
+        }
+    }
+    parser.finish(&Line::last(&source))
+}
+
+/// Replaces comments by spaces, so positions in the source stay the same.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        let comment = &rest[start..];
+        let len = comment.find("*/").map_or(comment.len(), |end| end + 2);
+        stripped.extend(
+            comment[..len]
+                .chars()
+                .map(|c| if c == '\n' { '\n' } else { ' ' }),
+        );
+        rest = &comment[len..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
@@ -0,0 +1,132 @@
+//! The classic, context-free deterministic L-System (D0L-System).
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+use rand::RngCore;
+
+use crate::rule::{self, Rule};
+
+/// A rule replacing a single element by a sequence of elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolRule<T> {
+    pub original: T,
+    pub replacement: Vec<T>,
+}
+
+impl<T> SymbolRule<T> {
+    pub fn new(original: T, replacement: Vec<T>) -> Self {
+        SymbolRule {
+            original,
+            replacement,
+        }
+    }
+}
+
+impl<T> From<(T, Vec<T>)> for SymbolRule<T> {
+    fn from((original, replacement): (T, Vec<T>)) -> Self {
+        SymbolRule::new(original, replacement)
+    }
+}
+
+impl<T: PartialEq + Clone> Rule<T> for SymbolRule<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        (input[at] == self.original).then_some(1)
+    }
+
+    fn produce(&self, _: &[T], _: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.extend_from_slice(&self.replacement);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+/// A simple, generic implementation of an L-System. (<https://en.wikipedia.org/wiki/L-system>)
+/// The alphabet will be all instances of T that actually occur in either the axiom or the rules
+/// There can be multiple rules attached to the same element, they are applied in order
+/// Any element without a rule is treated as a constant
+///
+/// ```
+/// use lsystem::deterministic::lsystem;
+///
+/// let rules = vec![('A', vec!['A', 'B']), ('B', vec!['A'])];
+/// assert_eq!(lsystem(vec!['A'], &rules, 3), vec!['A', 'B', 'A', 'A', 'B']);
+/// ```
+pub fn lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(T, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules: Vec<SymbolRule<T>> = rules.iter().cloned().map(SymbolRule::from).collect();
+    rule::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
+
+/// Like [`lsystem`], but rewrites large generations on several threads.
+/// The output is identical to that of [`lsystem`].
+#[cfg(feature = "parallel")]
+pub fn par_lsystem<T: PartialEq + Clone + Send + Sync>(
+    axiom: Vec<T>,
+    rules: &[(T, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules: Vec<SymbolRule<T>> = rules.iter().cloned().map(SymbolRule::from).collect();
+    crate::parallel::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
+
+/// A set of [`SymbolRule`]s indexed by the element they replace, so finding the rule for an
+/// element takes constant time instead of comparing it against every rule.
+/// As with [`lsystem`], only the first rule for an element applies.
+#[derive(Debug, Clone)]
+pub struct IndexedRules<T> {
+    rules: HashMap<T, Vec<T>>,
+}
+
+impl<T: Hash + Eq> IndexedRules<T> {
+    pub fn new(rules: impl IntoIterator<Item = (T, Vec<T>)>) -> Self {
+        let mut indexed = HashMap::new();
+        for (original, replacement) in rules {
+            indexed.entry(original).or_insert(replacement);
+        }
+        IndexedRules { rules: indexed }
+    }
+
+    /// The replacement for `original`, if there is a rule for it.
+    pub fn get(&self, original: &T) -> Option<&[T]> {
+        self.rules.get(original).map(Vec::as_slice)
+    }
+}
+
+impl<T: Hash + Eq + Clone> Rule<T> for IndexedRules<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        self.rules.contains_key(&input[at]).then_some(1)
+    }
+
+    fn produce(&self, input: &[T], matched: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.extend_from_slice(&self.rules[&input[matched.start]]);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+/// Like [`lsystem`], but looks up the rule for each element in an [`IndexedRules`], which is much
+/// faster for large sets of rules.
+///
+/// ```
+/// use lsystem::deterministic::indexed_lsystem;
+///
+/// let rules = vec![('A', vec!['A', 'B']), ('B', vec!['A'])];
+/// assert_eq!(indexed_lsystem(vec!['A'], &rules, 3), vec!['A', 'B', 'A', 'A', 'B']);
+/// ```
+pub fn indexed_lsystem<T: Hash + Eq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(T, Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules = [IndexedRules::new(rules.iter().cloned())];
+    rule::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
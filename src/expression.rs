@@ -0,0 +1,729 @@
+//! A small expression language for the guards and successor parameters of parametric rules, so
+//! parametric grammars can be written as data instead of closures.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use rand::{Rng, RngCore};
+
+use crate::parametric::{Condition, Env};
+
+/// A parsed arithmetic or boolean expression.
+///
+/// The syntax is that of C, with `^` for exponentiation:
+///
+/// - numbers like `2`, `0.5`, `.5` and `1e-3`
+/// - names of parameters, or of constants given to [`Expr::parse_with_constants`]
+/// - `+`, `-`, `*`, `/`, `%` and `^`, and the unary `-` and `+`
+/// - comparisons `<`, `<=`, `>`, `>=`, `==` and `!=`
+/// - `&&`, `||` and `!`, which treat non-zero values as true
+/// - the functions `sin`, `cos`, `tan`, `asin`, `acos`, `atan`, `atan2`, `sqrt`, `abs`, `exp`,
+///   `log`, `floor`, `ceil`, `pow`, `min` and `max`, with angles in radians
+/// - the random functions `rand()` (uniform in `[0, 1)`), `rand(a, b)` (uniform in `[a, b)`),
+///   `ran(x)` (uniform in `[0, x)`) and `nran(mean, deviation)` (normally distributed)
+///
+/// Booleans are numbers: comparisons evaluate to `1` or `0`. The random functions draw from the
+/// generator the rule is given, so a seeded expansion stays reproducible.
+///
+/// An `Expr` is an [`Expression`](crate::parametric::Expression) computing a parameter, and a
+/// [`Condition`] that holds when it evaluates to anything but zero or NaN. Names that are not
+/// bound when it is evaluated are NaN.
+///
+/// ```
+/// use lsystem::expression::Expr;
+/// use lsystem::parametric::{Env, Expression};
+///
+/// let expr = Expr::parse("max(t * 0.5, 1) + (t > 3)").unwrap();
+/// let mut env = Env::new();
+/// env.bind("t", 4.0);
+/// assert_eq!(expr.evaluate(&env, &mut rand::thread_rng()), 3.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Variable(String),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl BinaryOp {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            BinaryOp::Or => truth(is_true(a) || is_true(b)),
+            BinaryOp::And => truth(is_true(a) && is_true(b)),
+            BinaryOp::Eq => truth(a == b),
+            BinaryOp::Ne => truth(a != b),
+            BinaryOp::Lt => truth(a < b),
+            BinaryOp::Le => truth(a <= b),
+            BinaryOp::Gt => truth(a > b),
+            BinaryOp::Ge => truth(a >= b),
+            BinaryOp::Add => a + b,
+            BinaryOp::Sub => a - b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Div => a / b,
+            BinaryOp::Rem => a % b,
+            BinaryOp::Pow => a.powf(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sqrt,
+    Abs,
+    Exp,
+    Log,
+    Floor,
+    Ceil,
+    Pow,
+    Min,
+    Max,
+    Rand,
+    Ran,
+    Nran,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "asin" => Function::Asin,
+            "acos" => Function::Acos,
+            "atan" => Function::Atan,
+            "atan2" => Function::Atan2,
+            "sqrt" => Function::Sqrt,
+            "abs" => Function::Abs,
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "pow" => Function::Pow,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "rand" => Function::Rand,
+            "ran" => Function::Ran,
+            "nran" => Function::Nran,
+            _ => return None,
+        })
+    }
+
+    /// Whether the function accepts `n` arguments, and a description of what it accepts.
+    fn arity(self, n: usize) -> Result<(), &'static str> {
+        let (ok, expected) = match self {
+            Function::Atan2 | Function::Pow | Function::Nran => (n == 2, "2"),
+            Function::Min | Function::Max => (n >= 1, "at least 1"),
+            Function::Rand => (n == 0 || n == 2, "0 or 2"),
+            _ => (n == 1, "1"),
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(expected)
+        }
+    }
+
+    fn is_random(self) -> bool {
+        matches!(self, Function::Rand | Function::Ran | Function::Nran)
+    }
+
+    fn apply(self, args: &[f64], rng: &mut dyn RngCore) -> f64 {
+        match self {
+            Function::Sin => args[0].sin(),
+            Function::Cos => args[0].cos(),
+            Function::Tan => args[0].tan(),
+            Function::Asin => args[0].asin(),
+            Function::Acos => args[0].acos(),
+            Function::Atan => args[0].atan(),
+            Function::Atan2 => args[0].atan2(args[1]),
+            Function::Sqrt => args[0].sqrt(),
+            Function::Abs => args[0].abs(),
+            Function::Exp => args[0].exp(),
+            Function::Log => args[0].ln(),
+            Function::Floor => args[0].floor(),
+            Function::Ceil => args[0].ceil(),
+            Function::Pow => args[0].powf(args[1]),
+            Function::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            Function::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Function::Rand => match args {
+                [] => rng.gen::<f64>(),
+                _ => args[0] + rng.gen::<f64>() * (args[1] - args[0]),
+            },
+            Function::Ran => rng.gen::<f64>() * args[0],
+            Function::Nran => {
+                // Box-Muller transform
+                let u = 1.0 - rng.gen::<f64>();
+                let v = rng.gen::<f64>();
+                args[0] + args[1] * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+            }
+        }
+    }
+}
+
+fn is_true(value: f64) -> bool {
+    value != 0.0 && !value.is_nan()
+}
+
+fn truth(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+impl Node {
+    fn evaluate(&self, env: &Env, rng: &mut dyn RngCore) -> f64 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Variable(name) => env.get(name).unwrap_or(f64::NAN),
+            Node::Unary(UnaryOp::Neg, operand) => -operand.evaluate(env, rng),
+            Node::Unary(UnaryOp::Not, operand) => truth(!is_true(operand.evaluate(env, rng))),
+            // the right operand of && and || is only evaluated when needed, so it only draws
+            // random numbers when it matters
+            Node::Binary(BinaryOp::And, a, b) => {
+                truth(is_true(a.evaluate(env, rng)) && is_true(b.evaluate(env, rng)))
+            }
+            Node::Binary(BinaryOp::Or, a, b) => {
+                truth(is_true(a.evaluate(env, rng)) || is_true(b.evaluate(env, rng)))
+            }
+            Node::Binary(op, a, b) => {
+                let a = a.evaluate(env, rng);
+                op.apply(a, b.evaluate(env, rng))
+            }
+            Node::Call(function, args) => {
+                let args: Vec<f64> = args.iter().map(|arg| arg.evaluate(env, rng)).collect();
+                function.apply(&args, rng)
+            }
+        }
+    }
+
+    /// Replaces subexpressions without names or random functions by their value.
+    fn fold(self) -> Self {
+        let node = match self {
+            Node::Unary(op, operand) => Node::Unary(op, Box::new(operand.fold())),
+            Node::Binary(op, a, b) => Node::Binary(op, Box::new(a.fold()), Box::new(b.fold())),
+            Node::Call(function, args) => {
+                Node::Call(function, args.into_iter().map(Node::fold).collect())
+            }
+            leaf => return leaf,
+        };
+        let constant = match &node {
+            Node::Unary(_, operand) => matches!(**operand, Node::Number(_)),
+            Node::Binary(_, a, b) => {
+                matches!(**a, Node::Number(_)) && matches!(**b, Node::Number(_))
+            }
+            Node::Call(function, args) => {
+                !function.is_random() && args.iter().all(|arg| matches!(arg, Node::Number(_)))
+            }
+            _ => false,
+        };
+        if constant {
+            // evaluating constants draws no random numbers, so any generator will do
+            Node::Number(node.evaluate(&Env::new(), &mut rand::rngs::mock::StepRng::new(0, 0)))
+        } else {
+            node
+        }
+    }
+
+    fn variables<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Node::Number(_) => {}
+            Node::Variable(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Node::Unary(_, operand) => operand.variables(names),
+            Node::Binary(_, a, b) => {
+                a.variables(names);
+                b.variables(names);
+            }
+            Node::Call(_, args) => args.iter().for_each(|arg| arg.variables(names)),
+        }
+    }
+}
+
+impl Expr {
+    /// Parses `source` as a single expression.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        Expr::parse_with_constants(source, &HashMap::new())
+    }
+
+    /// Parses `source` as a single expression, replacing the names of `constants` by their
+    /// values. Constants take precedence over parameters of the same name.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use lsystem::expression::Expr;
+    ///
+    /// let constants = HashMap::from([("R".to_string(), 1.456)]);
+    /// let expr = Expr::parse_with_constants("s * R", &constants).unwrap();
+    /// assert_eq!(expr.variables(), ["s"]);
+    /// ```
+    pub fn parse_with_constants(
+        source: &str,
+        constants: &HashMap<String, f64>,
+    ) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: Lexer::new(source).tokens()?,
+            next: 0,
+            constants,
+            depth: 0,
+        };
+        let node = parser.expression()?;
+        parser.expect(Token::End)?;
+        Ok(Expr { node: node.fold() })
+    }
+
+    /// The names this expression refers to, in order of their first appearance.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.node.variables(&mut names);
+        names
+    }
+
+    /// The value of this expression, if it does not depend on parameters or random numbers.
+    pub fn constant(&self) -> Option<f64> {
+        match self.node {
+            Node::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl crate::parametric::Expression for Expr {
+    fn evaluate(&self, env: &Env, rng: &mut dyn RngCore) -> f64 {
+        self.node.evaluate(env, rng)
+    }
+}
+
+impl Condition for Expr {
+    fn holds(&self, env: &Env, rng: &mut dyn RngCore) -> bool {
+        is_true(self.node.evaluate(env, rng))
+    }
+}
+
+/// An error in the source of an [`Expr`], at the given byte offset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character that cannot start a token.
+    UnexpectedChar(char),
+    /// A token other than the one that was expected, described by `found`.
+    Expected {
+        expected: &'static str,
+        found: String,
+    },
+    InvalidNumber(String),
+    UnknownFunction(String),
+    /// A function called with the wrong number of arguments.
+    Arity {
+        function: String,
+        expected: &'static str,
+        found: usize,
+    },
+    /// Operators or parentheses nested more than [`MAX_DEPTH`] levels deep.
+    TooDeep,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character `{c}`"),
+            ParseErrorKind::Expected { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ParseErrorKind::InvalidNumber(number) => write!(f, "invalid number `{number}`"),
+            ParseErrorKind::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            ParseErrorKind::Arity {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{function}` takes {expected} argument(s), but {found} were given"
+            ),
+            ParseErrorKind::TooDeep => {
+                write!(f, "expressions nest at most {MAX_DEPTH} levels deep")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(&'static str),
+    Open,
+    Close,
+    Comma,
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "`{value}`"),
+            Token::Name(name) => write!(f, "`{name}`"),
+            Token::Operator(op) => write!(f, "`{op}`"),
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::End => write!(f, "the end of the expression"),
+        }
+    }
+}
+
+/// Operators, longest first so that `<=` is not read as `<`.
+const OPERATORS: [&str; 18] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "^", "(", ")", ",",
+];
+
+struct Lexer<'s> {
+    source: &'s str,
+    position: usize,
+}
+
+impl<'s> Lexer<'s> {
+    fn new(source: &'s str) -> Self {
+        Lexer {
+            source,
+            position: 0,
+        }
+    }
+
+    fn tokens(mut self) -> Result<Vec<(usize, Token)>, ParseError> {
+        let mut tokens = Vec::new();
+        loop {
+            let rest = &self.source[self.position..];
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            let start = self.position;
+            let Some(c) = trimmed.chars().next() else {
+                tokens.push((start, Token::End));
+                return Ok(tokens);
+            };
+            let token = if c.is_ascii_digit() || c == '.' {
+                self.number(trimmed)?
+            } else if c.is_alphabetic() || c == '_' {
+                let len = trimmed
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(trimmed.len());
+                self.position += len;
+                Token::Name(trimmed[..len].to_string())
+            } else {
+                let op =
+                    OPERATORS
+                        .iter()
+                        .find(|op| trimmed.starts_with(*op))
+                        .ok_or(ParseError {
+                            position: start,
+                            kind: ParseErrorKind::UnexpectedChar(c),
+                        })?;
+                self.position += op.len();
+                match *op {
+                    "(" => Token::Open,
+                    ")" => Token::Close,
+                    "," => Token::Comma,
+                    op => Token::Operator(op),
+                }
+            };
+            tokens.push((start, token));
+        }
+    }
+
+    fn number(&mut self, rest: &str) -> Result<Token, ParseError> {
+        let bytes = rest.as_bytes();
+        let mut len = 0;
+        while len < bytes.len() && (bytes[len].is_ascii_digit() || bytes[len] == b'.') {
+            len += 1;
+        }
+        if len < bytes.len() && (bytes[len] == b'e' || bytes[len] == b'E') {
+            let mut exponent = len + 1;
+            if exponent < bytes.len() && (bytes[exponent] == b'+' || bytes[exponent] == b'-') {
+                exponent += 1;
+            }
+            if exponent < bytes.len() && bytes[exponent].is_ascii_digit() {
+                len = exponent;
+                while len < bytes.len() && bytes[len].is_ascii_digit() {
+                    len += 1;
+                }
+            }
+        }
+        let text = &rest[..len];
+        let value = text.parse().map_err(|_| ParseError {
+            position: self.position,
+            kind: ParseErrorKind::InvalidNumber(text.to_string()),
+        })?;
+        self.position += len;
+        Ok(Token::Number(value))
+    }
+}
+
+/// How deeply operators and parentheses can nest in an expression, so that parsing and
+/// evaluating it cannot overflow the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// A recursive descent parser, which climbs the precedence of the binary operators.
+struct Parser<'c> {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    constants: &'c HashMap<String, f64>,
+    /// The current level of nesting, see [`MAX_DEPTH`].
+    depth: usize,
+}
+
+/// Binary operators from the lowest to the highest precedence, all left-associative.
+const LEVELS: [&[(&str, BinaryOp)]; 5] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[
+        ("==", BinaryOp::Eq),
+        ("!=", BinaryOp::Ne),
+        ("<", BinaryOp::Lt),
+        ("<=", BinaryOp::Le),
+        (">", BinaryOp::Gt),
+        (">=", BinaryOp::Ge),
+    ],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[
+        ("*", BinaryOp::Mul),
+        ("/", BinaryOp::Div),
+        ("%", BinaryOp::Rem),
+    ],
+];
+
+impl Parser<'_> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].1
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.next].0
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].1.clone();
+        if token != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &'static str) -> ParseError {
+        ParseError {
+            position: self.position(),
+            kind: ParseErrorKind::Expected {
+                expected,
+                found: self.peek().to_string(),
+            },
+        }
+    }
+
+    /// Goes one level deeper, which a parse error ends, so only successful parses leave again.
+    fn nest(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ParseError {
+                position: self.position(),
+                kind: ParseErrorKind::TooDeep,
+            });
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ParseError> {
+        if *self.peek() == token {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error(match token {
+                Token::Close => "`)`",
+                Token::End => "an operator or the end of the expression",
+                _ => "a different token",
+            }))
+        }
+    }
+
+    fn expression(&mut self) -> Result<Node, ParseError> {
+        self.binary(0)
+    }
+
+    /// Parses operands joined by binary operators of the precedence `level` or higher, counted
+    /// in [`LEVELS`], by precedence climbing.
+    fn binary(&mut self, level: usize) -> Result<Node, ParseError> {
+        let depth = self.depth;
+        let mut node = self.unary()?;
+        while let Token::Operator(op) = *self.peek() {
+            let Some((precedence, binary)) =
+                LEVELS
+                    .iter()
+                    .enumerate()
+                    .skip(level)
+                    .find_map(|(precedence, operators)| {
+                        let (_, binary) = operators.iter().find(|(symbol, _)| *symbol == op)?;
+                        Some((precedence, *binary))
+                    })
+            else {
+                break;
+            };
+            // each operator nests the operands before it one level deeper
+            self.nest()?;
+            self.advance();
+            let right = self.binary(precedence + 1)?;
+            node = Node::Binary(binary, Box::new(node), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        // every way an expression nests, by operators, parentheses or arguments, leads through
+        // here
+        self.nest()?;
+        let node = match *self.peek() {
+            Token::Operator("-") => {
+                self.advance();
+                Ok(Node::Unary(UnaryOp::Neg, Box::new(self.unary()?)))
+            }
+            Token::Operator("!") => {
+                self.advance();
+                Ok(Node::Unary(UnaryOp::Not, Box::new(self.unary()?)))
+            }
+            Token::Operator("+") => {
+                self.advance();
+                self.unary()
+            }
+            _ => self.power(),
+        };
+        self.depth -= 1;
+        node
+    }
+
+    /// Exponentiation binds tighter than the unary operators and is right-associative, so
+    /// `-2^2` is `-(2^2)` and `2^3^2` is `2^(3^2)`.
+    fn power(&mut self) -> Result<Node, ParseError> {
+        let base = self.primary()?;
+        if *self.peek() == Token::Operator("^") {
+            self.advance();
+            let exponent = self.unary()?;
+            return Ok(Node::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node, ParseError> {
+        let position = self.position();
+        match self.peek().clone() {
+            Token::Number(value) => {
+                self.advance();
+                Ok(Node::Number(value))
+            }
+            Token::Open => {
+                self.advance();
+                let node = self.expression()?;
+                self.expect(Token::Close)?;
+                Ok(node)
+            }
+            Token::Name(name) => {
+                self.advance();
+                if *self.peek() == Token::Open {
+                    self.call(name, position)
+                } else if let Some(&value) = self.constants.get(&name) {
+                    Ok(Node::Number(value))
+                } else {
+                    Ok(Node::Variable(name))
+                }
+            }
+            _ => Err(self.error("a number, a name or `(`")),
+        }
+    }
+
+    fn call(&mut self, name: String, position: usize) -> Result<Node, ParseError> {
+        let function = Function::from_name(&name).ok_or(ParseError {
+            position,
+            kind: ParseErrorKind::UnknownFunction(name.clone()),
+        })?;
+        self.advance();
+        let mut args = Vec::new();
+        if *self.peek() != Token::Close {
+            args.push(self.expression()?);
+            while *self.peek() == Token::Comma {
+                self.advance();
+                args.push(self.expression()?);
+            }
+        }
+        if *self.peek() != Token::Close {
+            return Err(self.error("`,` or `)`"));
+        }
+        self.advance();
+        function.arity(args.len()).map_err(|expected| ParseError {
+            position,
+            kind: ParseErrorKind::Arity {
+                function: name,
+                expected,
+                found: args.len(),
+            },
+        })?;
+        Ok(Node::Call(function, args))
+    }
+}
@@ -0,0 +1,14 @@
+//! Number formatting shared by the text based export formats.
+
+/// Formats a number with at most `precision` decimal places, and without trailing zeros.
+pub(crate) fn number(value: f64, precision: usize) -> String {
+    let formatted = format!("{value:.precision$}");
+    let trimmed = match formatted.contains('.') {
+        true => formatted.trim_end_matches('0').trim_end_matches('.'),
+        false => &formatted,
+    };
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
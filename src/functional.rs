@@ -0,0 +1,87 @@
+//! L-Systems whose replacements are computed by arbitrary functions.
+
+use std::ops::Range;
+
+use rand::RngCore;
+
+use crate::limits::{self, ExpansionError, Limits};
+use crate::rule::{self, Rule};
+
+/// A rule replacing a slice of elements by the result of a function applied to that slice.
+#[derive(Clone)]
+pub struct FnRule<T, F> {
+    pub original: Vec<T>,
+    pub transform: F,
+}
+
+impl<T, F: Fn(Vec<T>) -> Vec<T>> FnRule<T, F> {
+    pub fn new(original: Vec<T>, transform: F) -> Self {
+        FnRule {
+            original,
+            transform,
+        }
+    }
+}
+
+impl<T: PartialEq + Clone, F: Fn(Vec<T>) -> Vec<T>> Rule<T> for FnRule<T, F> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        input[at..]
+            .starts_with(&self.original)
+            .then_some(self.original.len())
+    }
+
+    fn produce(&self, input: &[T], matched: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.append(&mut (self.transform)(input[matched].to_vec()));
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(self.original.len())
+    }
+}
+
+/// A fully generic implementation of an L-System
+/// axiom: starting state of the system
+/// rules: tuple of a pattern of what is to be transformed and a function of the transformation to be applied
+///
+/// ```
+/// use lsystem::functional::arbitrary_lsystem;
+///
+/// let double = |orig: Vec<char>| orig.repeat(2);
+/// assert_eq!(arbitrary_lsystem(vec!['A'], &[(vec!['A'], double)], 3), vec!['A'; 8]);
+/// ```
+pub fn arbitrary_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, impl Fn(Vec<T>) -> Vec<T>)],
+    iterations: u32,
+) -> Vec<T> {
+    let rules: Vec<_> = rules
+        .iter()
+        .map(|(original, transform)| FnRule::new(original.clone(), transform))
+        .collect();
+    rule::expand(axiom, &rules, iterations, &mut rand::thread_rng())
+}
+
+/// Like [`arbitrary_lsystem`], but stops as soon as a generation exceeds `limits`, returning the
+/// last generation that stayed within them.
+///
+/// ```
+/// use lsystem::functional::try_arbitrary_lsystem;
+/// use lsystem::limits::Limits;
+///
+/// let double = |orig: Vec<char>| orig.repeat(2);
+/// let limits = Limits::new().max_symbols(100);
+/// let error = try_arbitrary_lsystem(vec!['A'], &[(vec!['A'], double)], 64, &limits).unwrap_err();
+/// assert_eq!(error.last.len(), 64);
+/// ```
+pub fn try_arbitrary_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, impl Fn(Vec<T>) -> Vec<T>)],
+    iterations: u32,
+    limits: &Limits,
+) -> Result<Vec<T>, ExpansionError<T>> {
+    let rules: Vec<_> = rules
+        .iter()
+        .map(|(original, transform)| FnRule::new(original.clone(), transform))
+        .collect();
+    limits::try_expand(axiom, &rules, iterations, &mut rand::thread_rng(), limits)
+}
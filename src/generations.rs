@@ -0,0 +1,63 @@
+//! Incremental iteration over the generations of an L-System.
+
+use std::mem;
+
+use rand::RngCore;
+
+use crate::rule::{self, BoxedRule, Randomness};
+
+/// An iterator over the generations of an [`LSystem`](crate::LSystem), starting with the axiom.
+///
+/// Every generation is computed from the previous one, so producing the first `n` generations
+/// takes as much work as expanding generation `n` directly. The iterator never ends, use
+/// [`Iterator::take`] to limit it.
+///
+/// Iterating yields a clone of every generation; [`Generations::next_ref`] advances without
+/// cloning and borrows the generation instead.
+pub struct Generations<'a, T, R> {
+    rules: &'a [BoxedRule<T>],
+    rng: R,
+    current: Vec<T>,
+    next: Vec<T>,
+    generation: Option<usize>,
+}
+
+impl<'a, T: Clone, R: RngCore> Generations<'a, T, R> {
+    pub(crate) fn new(axiom: &[T], rules: &'a [BoxedRule<T>], rng: R) -> Self {
+        Generations {
+            rules,
+            rng,
+            current: axiom.to_vec(),
+            next: Vec::with_capacity(axiom.len() * 2),
+            generation: None,
+        }
+    }
+
+    /// Advances to the next generation and borrows it.
+    pub fn next_ref(&mut self) -> &[T] {
+        match self.generation {
+            None => self.generation = Some(0),
+            Some(n) => {
+                self.next.clear();
+                let mut randomness = Randomness::Shared(&mut self.rng);
+                rule::rewrite(&self.current, self.rules, &mut randomness, &mut self.next);
+                mem::swap(&mut self.current, &mut self.next);
+                self.generation = Some(n + 1);
+            }
+        }
+        &self.current
+    }
+
+    /// The number of the generation that was yielded last, `None` before the axiom was yielded.
+    pub fn generation(&self) -> Option<usize> {
+        self.generation
+    }
+}
+
+impl<T: Clone, R: RngCore> Iterator for Generations<'_, T, R> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        Some(self.next_ref().to_vec())
+    }
+}
@@ -0,0 +1,555 @@
+//! A text format for L-Systems over `char` symbols.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::branching::Branching;
+use crate::expression::Expr;
+use crate::parametric::{Module, ParametricRule, Pattern, Template};
+use crate::system::LSystem;
+
+/// An L-System parsed from text, with [`Module`]s of `char` symbols as elements.
+///
+/// A grammar is a sequence of lines, each of which is one of:
+///
+/// - `axiom: F(1)[+A]B`, the axiom, which is required
+/// - `left < predecessor > right : condition -> successor : probability`, a rule, where all but
+///   the predecessor, the arrow and the successor are optional
+/// - `const NAME = expression`, a constant that can be used in all later expressions
+/// - `ignore: +-`, symbols that are skipped when matching context
+/// - `iterations: 5`, the number of iterations the grammar is meant to be expanded for
+///
+/// Everything from a `#` to the end of a line is a comment, and whitespace is insignificant.
+///
+/// Modules are single characters, optionally followed by their parameters in parentheses. In the
+/// predecessor and the context these are names, which are bound to the actual parameters of the
+/// matched modules, and in the successor and the axiom they are expressions in the [`expression`]
+/// language; the condition is an expression too. Context is matched along the branches delimited
+/// by `[` and `]`, see [`Branching`].
+///
+/// As in all L-Systems of this crate, the first rule that matches a module wins. Consecutive rules
+/// with the same left-hand side that are all followed by a probability form a single stochastic
+/// rule, which chooses one of their successors with probabilities proportional to the given ones.
+///
+/// [`expression`]: crate::expression
+///
+/// ```
+/// use lsystem::grammar::Grammar;
+///
+/// let grammar: Grammar = "
+///     axiom: B(2)A(4,4)  # The Algorithmic Beauty of Plants, section 1.10
+///     A(x,y) : y <= 3 -> A(x*2, x+y)
+///     A(x,y) : y > 3 -> B(x)A(x/y, 0)
+///     B(x) : x < 1 -> C
+///     B(x) : x >= 1 -> B(x-1)
+/// "
+/// .parse()
+/// .unwrap();
+/// let generation: String = grammar.into_lsystem().expand(4).iter().map(|m| m.to_string()).collect();
+/// assert_eq!(generation, "CB(1)A(8,7)");
+/// ```
+pub struct Grammar {
+    pub axiom: Vec<Module<char>>,
+    pub rules: Vec<ParametricRule<char>>,
+    pub constants: HashMap<String, f64>,
+    pub ignore: Vec<char>,
+    pub iterations: Option<u32>,
+}
+
+impl Grammar {
+    pub fn parse(source: &str) -> Result<Self, GrammarError> {
+        let mut parser = GrammarParser::new("->", false);
+        for (index, text) in source.lines().enumerate() {
+            let line = Line {
+                number: index + 1,
+                text,
+            };
+            let content = Span {
+                start: 0,
+                text: text.split('#').next().unwrap_or(""),
+            }
+            .trim();
+            if !content.text.is_empty() {
+                parser.line(&line, content)?;
+            }
+        }
+        parser.finish(&Line::last(source))
+    }
+
+    /// An [`LSystem`] with the axiom and the rules of this grammar.
+    pub fn into_lsystem(self) -> LSystem<Module<char>> {
+        let mut system = LSystem::new(self.axiom);
+        for rule in self.rules {
+            system.push_rule(rule);
+        }
+        system
+    }
+}
+
+impl FromStr for Grammar {
+    type Err = GrammarError;
+
+    fn from_str(source: &str) -> Result<Self, GrammarError> {
+        Grammar::parse(source)
+    }
+}
+
+/// An error in the source of a [`Grammar`], at the given line and column, both counting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarError {
+    pub line: usize,
+    pub column: usize,
+    /// The line the error is in.
+    pub snippet: String,
+    pub message: String,
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )?;
+        writeln!(f, "  {}", self.snippet)?;
+        write!(f, "  {:>width$}", "^", width = self.column)
+    }
+}
+
+impl Error for GrammarError {}
+
+pub(crate) struct Line<'a> {
+    pub(crate) number: usize,
+    pub(crate) text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// The last line of `source`, where the end of the input is.
+    pub(crate) fn last(source: &'a str) -> Self {
+        let (index, text) = source.lines().enumerate().last().unwrap_or((0, ""));
+        Line {
+            number: index + 1,
+            text,
+        }
+    }
+
+    /// An error at the byte offset `at` of this line.
+    pub(crate) fn error(&self, at: usize, message: impl Into<String>) -> GrammarError {
+        GrammarError {
+            line: self.number,
+            column: self.text[..at].chars().count() + 1,
+            snippet: self.text.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A part of a line, starting at the byte offset `start`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Span<'a> {
+    pub(crate) start: usize,
+    pub(crate) text: &'a str,
+}
+
+impl<'a> Span<'a> {
+    pub(crate) fn trim(self) -> Self {
+        let trimmed = self.text.trim_start();
+        Span {
+            start: self.start + self.text.len() - trimmed.len(),
+            text: trimmed.trim_end(),
+        }
+    }
+
+    fn end(self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// The part before and the part after `text[at..at + len]`.
+    fn split(self, at: usize, len: usize) -> (Self, Self) {
+        (
+            Span {
+                start: self.start,
+                text: &self.text[..at],
+            },
+            Span {
+                start: self.start + at + len,
+                text: &self.text[at + len..],
+            },
+        )
+    }
+
+    /// The byte offsets of all occurrences of `pattern` outside parentheses.
+    fn find_all(self, pattern: &str) -> Vec<usize> {
+        let mut depth = 0usize;
+        let mut found = Vec::new();
+        for (i, c) in self.text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ if depth == 0 && self.text[i..].starts_with(pattern) => found.push(i),
+                _ => {}
+            }
+        }
+        found
+    }
+
+    /// Splits at the first occurrence of `pattern` outside parentheses.
+    pub(crate) fn split_first(self, pattern: &str) -> Option<(Self, Self)> {
+        let at = *self.find_all(pattern).first()?;
+        Some(self.split(at, pattern.len()))
+    }
+
+    /// Splits at the last occurrence of `pattern` outside parentheses.
+    fn split_last(self, pattern: &str) -> Option<(Self, Self)> {
+        let at = *self.find_all(pattern).last()?;
+        Some(self.split(at, pattern.len()))
+    }
+
+    pub(crate) fn strip_prefix(self, prefix: &str) -> Option<Self> {
+        self.text.strip_prefix(prefix).map(|text| Span {
+            start: self.start + prefix.len(),
+            text,
+        })
+    }
+}
+
+/// A module as written in the source: its symbol, and the parts between the commas of its
+/// parameter list.
+struct RawModule<'a> {
+    symbol: char,
+    params: Vec<Span<'a>>,
+}
+
+fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Splits a sequence of modules like `F(x,1)[+A]`.
+fn modules<'a>(line: &Line, span: Span<'a>) -> Result<Vec<RawModule<'a>>, GrammarError> {
+    let mut modules = Vec::new();
+    let mut chars = span.text.char_indices().peekable();
+    while let Some((i, symbol)) = chars.next() {
+        let at = span.start + i;
+        if symbol.is_whitespace() {
+            continue;
+        }
+        if matches!(symbol, '(' | ')' | ',' | ':') {
+            return Err(line.error(at, format!("unexpected `{symbol}`")));
+        }
+        let mut params = Vec::new();
+        if let Some(&(open, '(')) = chars.peek() {
+            let mut depth = 0;
+            let close = span.text[open..]
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .map(|(j, _)| open + j)
+                .ok_or_else(|| line.error(span.start + open, "unclosed `(`"))?;
+            let list = Span {
+                start: span.start + open + 1,
+                text: &span.text[open + 1..close],
+            };
+            let mut rest = list;
+            while let Some((param, after)) = rest.split_first(",") {
+                params.push(param.trim());
+                rest = after;
+            }
+            params.push(rest.trim());
+            if let Some(empty) = params.iter().find(|param| param.text.is_empty()) {
+                return Err(line.error(empty.start, "expected a parameter"));
+            }
+            while chars.peek().is_some_and(|&(j, _)| j <= close) {
+                chars.next();
+            }
+        }
+        modules.push(RawModule { symbol, params });
+    }
+    Ok(modules)
+}
+
+/// A rule as parsed from a single line.
+struct ParsedRule {
+    /// The left-hand side without whitespace, to find the alternatives of stochastic rules.
+    key: String,
+    left: Vec<Pattern<char>>,
+    predecessor: Pattern<char>,
+    right: Vec<Pattern<char>>,
+    condition: Option<Expr>,
+    successor: Vec<Template<char>>,
+    /// The probability, and an error pointing at it in case it is invalid.
+    probability: Option<(f64, GrammarError)>,
+}
+
+/// Parses the parts of a grammar, which can be arranged differently by other formats.
+pub(crate) struct GrammarParser {
+    axiom: Option<Vec<Module<char>>>,
+    rules: Vec<ParsedRule>,
+    constants: HashMap<String, f64>,
+    ignore: Vec<char>,
+    iterations: Option<u32>,
+    /// The arrow between the left-hand side and the successor of a rule.
+    arrow: &'static str,
+    /// Whether a `*` stands for an empty context or condition.
+    wildcards: bool,
+}
+
+impl GrammarParser {
+    pub(crate) fn new(arrow: &'static str, wildcards: bool) -> Self {
+        GrammarParser {
+            axiom: None,
+            rules: Vec::new(),
+            constants: HashMap::new(),
+            ignore: Vec::new(),
+            iterations: None,
+            arrow,
+            wildcards,
+        }
+    }
+
+    fn line(&mut self, line: &Line, content: Span) -> Result<(), GrammarError> {
+        if let Some(rest) = content.strip_prefix("axiom:") {
+            self.axiom(line, content.start, rest)
+        } else if let Some(rest) = content.strip_prefix("ignore:") {
+            self.ignore(rest);
+            Ok(())
+        } else if let Some(rest) = content.strip_prefix("iterations:") {
+            self.iterations(line, rest.trim())
+        } else if let Some(rest) = content.strip_prefix("const ") {
+            let (name, value) = rest
+                .split_first("=")
+                .ok_or_else(|| line.error(rest.end(), "expected `=`"))?;
+            self.define(line, name.trim(), value.trim())
+        } else {
+            self.rule(line, content)
+        }
+    }
+
+    /// Parses the modules of the axiom; `at` is where the directive starts.
+    pub(crate) fn axiom(
+        &mut self,
+        line: &Line,
+        at: usize,
+        modules: Span,
+    ) -> Result<(), GrammarError> {
+        if self.axiom.is_some() {
+            return Err(line.error(at, "the axiom is defined twice"));
+        }
+        let axiom = self::modules(line, modules)?
+            .into_iter()
+            .map(|module| {
+                let params = module
+                    .params
+                    .iter()
+                    .map(|param| self.constant(line, *param))
+                    .collect::<Result<_, _>>()?;
+                Ok(Module::new(module.symbol, params))
+            })
+            .collect::<Result<_, _>>()?;
+        self.axiom = Some(axiom);
+        Ok(())
+    }
+
+    pub(crate) fn ignore(&mut self, symbols: Span) {
+        self.ignore
+            .extend(symbols.text.chars().filter(|c| !c.is_whitespace()));
+    }
+
+    pub(crate) fn iterations(&mut self, line: &Line, value: Span) -> Result<(), GrammarError> {
+        let iterations = self
+            .constant(line, value)
+            .ok()
+            .filter(|n| n.fract() == 0.0 && (0.0..=f64::from(u32::MAX)).contains(n))
+            .ok_or_else(|| line.error(value.start, "expected a number of iterations"))?;
+        self.iterations = Some(iterations as u32);
+        Ok(())
+    }
+
+    /// Defines a constant that can be used in all later expressions.
+    pub(crate) fn define(
+        &mut self,
+        line: &Line,
+        name: Span,
+        value: Span,
+    ) -> Result<(), GrammarError> {
+        if !is_name(name.text) {
+            return Err(line.error(name.start, "expected the name of the constant"));
+        }
+        let value = self.constant(line, value)?;
+        self.constants.insert(name.text.to_string(), value);
+        Ok(())
+    }
+
+    fn expr(&self, line: &Line, span: Span) -> Result<Expr, GrammarError> {
+        if span.text.is_empty() {
+            return Err(line.error(span.start, "expected an expression"));
+        }
+        Expr::parse_with_constants(span.text, &self.constants)
+            .map_err(|error| line.error(span.start + error.position, error.kind.to_string()))
+    }
+
+    /// Parses an expression that has to evaluate to a constant.
+    fn constant(&self, line: &Line, span: Span) -> Result<f64, GrammarError> {
+        self.expr(line, span)?
+            .constant()
+            .ok_or_else(|| line.error(span.start, "expected a constant expression"))
+    }
+
+    /// Parses an expression that can only refer to the given names.
+    fn bound_expr(&self, line: &Line, span: Span, names: &[&str]) -> Result<Expr, GrammarError> {
+        let expr = self.expr(line, span)?;
+        if let Some(unknown) = expr.variables().iter().find(|name| !names.contains(name)) {
+            return Err(line.error(span.start, format!("unknown parameter `{unknown}`")));
+        }
+        Ok(expr)
+    }
+
+    fn patterns(&self, line: &Line, span: Span) -> Result<Vec<Pattern<char>>, GrammarError> {
+        modules(line, span)?
+            .into_iter()
+            .map(|module| {
+                if let Some(param) = module.params.iter().find(|param| !is_name(param.text)) {
+                    return Err(line.error(param.start, "expected a parameter name"));
+                }
+                Ok(Pattern::new(
+                    module.symbol,
+                    module.params.iter().map(|param| param.text),
+                ))
+            })
+            .collect()
+    }
+
+    pub(crate) fn rule(&mut self, line: &Line, content: Span) -> Result<(), GrammarError> {
+        let (lhs, rhs) = content
+            .split_first(self.arrow)
+            .ok_or_else(|| line.error(content.end(), format!("expected `{}`", self.arrow)))?;
+        // a missing part, or a wildcard standing for it
+        let given = |part: Span<'_>| -> bool { !(self.wildcards && part.text.trim() == "*") };
+        let (context, condition) = match lhs.split_first(":") {
+            Some((context, condition)) => (context, Some(condition.trim()).filter(|c| given(*c))),
+            None => (lhs, None),
+        };
+        let (left, rest) = match context.split_first("<") {
+            Some((left, rest)) => (Some(left).filter(|l| given(*l)), rest),
+            None => (None, context),
+        };
+        let (predecessor, right) = match rest.split_first(">") {
+            Some((predecessor, right)) => (predecessor, Some(right).filter(|r| given(*r))),
+            None => (rest, None),
+        };
+
+        let mut predecessor = self.patterns(line, predecessor)?;
+        if predecessor.len() != 1 {
+            return Err(line.error(
+                rest.trim().start,
+                "expected a single module as the predecessor",
+            ));
+        }
+        let predecessor = predecessor.remove(0);
+        let left = match left {
+            Some(left) => self.patterns(line, left)?,
+            None => Vec::new(),
+        };
+        let right = match right {
+            Some(right) => self.patterns(line, right)?,
+            None => Vec::new(),
+        };
+        let names: Vec<&str> = left
+            .iter()
+            .chain([&predecessor])
+            .chain(&right)
+            .flat_map(|pattern| pattern.params.iter().map(String::as_str))
+            .collect();
+
+        let condition = condition
+            .map(|condition| self.bound_expr(line, condition, &names))
+            .transpose()?;
+        let (successor, probability) = match rhs.split_last(":") {
+            Some((successor, probability)) => {
+                let probability = probability.trim();
+                let value = self.constant(line, probability)?;
+                (successor, Some((value, line.error(probability.start, ""))))
+            }
+            None => (rhs, None),
+        };
+        let successor = modules(line, successor)?
+            .into_iter()
+            .map(|module| {
+                module
+                    .params
+                    .iter()
+                    .try_fold(Template::new(module.symbol), |template, param| {
+                        Ok(template.param(self.bound_expr(line, *param, &names)?))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        self.rules.push(ParsedRule {
+            key: lhs.text.split_whitespace().collect(),
+            left,
+            predecessor,
+            right,
+            condition,
+            successor,
+            probability,
+        });
+        Ok(())
+    }
+
+    /// Builds the grammar once all lines are parsed, reporting what is missing at the `end` of
+    /// the input.
+    pub(crate) fn finish(self, end: &Line) -> Result<Grammar, GrammarError> {
+        let axiom = self
+            .axiom
+            .ok_or_else(|| end.error(end.text.len(), "the grammar has no axiom"))?;
+
+        let mut rules = Vec::new();
+        let mut parsed = self.rules.into_iter().peekable();
+        while let Some(first) = parsed.next() {
+            let mut rule = match first.probability {
+                None => ParametricRule::new(first.predecessor, first.successor),
+                Some((probability, location)) => {
+                    let mut alternatives = vec![(first.successor, probability)];
+                    while let Some(next) =
+                        parsed.next_if(|next| next.key == first.key && next.probability.is_some())
+                    {
+                        alternatives.push((next.successor, next.probability.unwrap().0));
+                    }
+                    ParametricRule::stochastic(first.predecessor, alternatives).map_err(
+                        |error| GrammarError {
+                            message: error.to_string(),
+                            ..location
+                        },
+                    )?
+                }
+            };
+            if let Some(condition) = first.condition {
+                rule = rule.with_condition(condition);
+            }
+            if !first.left.is_empty() || !first.right.is_empty() {
+                rule = rule
+                    .with_left(first.left)
+                    .with_right(first.right)
+                    .with_branching(Branching::brackets().ignoring(self.ignore.iter().copied()));
+            }
+            rules.push(rule);
+        }
+
+        Ok(Grammar {
+            axiom,
+            rules,
+            constants: self.constants,
+            ignore: self.ignore,
+            iterations: self.iterations,
+        })
+    }
+}
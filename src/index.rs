@@ -0,0 +1,119 @@
+//! Random access into generations of context-free, deterministic L-Systems without expanding them.
+
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::alphabet::Alphabet;
+
+/// Answers queries about single elements or windows of a generation of an L-System with
+/// `(T, Vec<T>)` rules, as expanded by [`lsystem`](crate::deterministic::lsystem).
+///
+/// Every query first computes how long the expansion of each symbol of the alphabet is after each
+/// number of iterations, and then descends from the axiom straight to the requested elements. A
+/// query for generation `n` therefore takes `O(n × size of the rules)` time, even for generations
+/// far too long to ever be expanded. Lengths are counted in `u128` and saturate at `u128::MAX`.
+///
+/// ```
+/// use lsystem::index::GenerationIndex;
+///
+/// // the Thue-Morse sequence: the k-th element is B exactly if k has an odd number of ones
+/// let index = GenerationIndex::new(&['A'], &[('A', vec!['A', 'B']), ('B', vec!['B', 'A'])]);
+/// assert_eq!(index.len(100), 1 << 100);
+/// assert_eq!(index.get(100, (1 << 99) + 3), Some('B'));
+/// assert_eq!(index.slice(100, 4..8), vec!['B', 'A', 'A', 'B']);
+/// ```
+pub struct GenerationIndex<T> {
+    alphabet: Alphabet<T>,
+    axiom: Vec<usize>,
+}
+
+impl<T: Hash + Eq + Clone> GenerationIndex<T> {
+    pub fn new(axiom: &[T], rules: &[(T, Vec<T>)]) -> Self {
+        let alphabet = Alphabet::new(axiom, rules);
+        let axiom = alphabet.encode(axiom);
+        GenerationIndex { alphabet, axiom }
+    }
+
+    /// The number of elements of the given generation.
+    pub fn len(&self, generation: u32) -> u128 {
+        let lengths = self.lengths(generation);
+        sum(self.axiom.iter().map(|&s| lengths[generation as usize][s]))
+    }
+
+    /// Whether the given generation has no elements at all.
+    pub fn is_empty(&self, generation: u32) -> bool {
+        self.len(generation) == 0
+    }
+
+    /// The element at `index` of the given generation, `None` if the generation is shorter.
+    pub fn get(&self, generation: u32, index: u128) -> Option<T> {
+        self.slice(generation, index..index.saturating_add(1)).pop()
+    }
+
+    /// The elements in `range` of the given generation, truncated to the length of the generation.
+    pub fn slice(&self, generation: u32, range: Range<u128>) -> Vec<T> {
+        let lengths = self.lengths(generation);
+        let mut window = Window {
+            skip: range.start,
+            take: range.end.saturating_sub(range.start),
+            out: Vec::new(),
+        };
+        self.emit(&lengths, generation as usize, &mut window);
+        window.out
+    }
+
+    /// `lengths[d][s]` is the length of the expansion of symbol `s` after `d` iterations.
+    fn lengths(&self, generation: u32) -> Vec<Vec<u128>> {
+        let mut lengths = vec![vec![1; self.alphabet.len()]];
+        for _ in 0..generation {
+            let previous = lengths.last().unwrap();
+            let next = self
+                .alphabet
+                .productions
+                .iter()
+                .map(|production| sum(production.iter().map(|&s| previous[s])))
+                .collect();
+            lengths.push(next);
+        }
+        lengths
+    }
+
+    /// Descends into the expansion of the axiom after `generation` iterations, skipping and
+    /// collecting elements as requested by `window`.
+    fn emit(&self, lengths: &[Vec<u128>], generation: usize, window: &mut Window<T>) {
+        // the symbols still to visit at each depth, kept on the heap rather than the call stack
+        // since there is a level for every generation
+        let mut stack: Vec<(usize, &[usize])> = vec![(generation, &self.axiom)];
+        while let Some((depth, symbols)) = stack.last_mut() {
+            let depth = *depth;
+            let Some((&symbol, rest)) = symbols.split_first() else {
+                stack.pop();
+                continue;
+            };
+            *symbols = rest;
+            if window.take == 0 {
+                return;
+            }
+            let len = lengths[depth][symbol];
+            if window.skip >= len {
+                window.skip -= len;
+            } else if depth == 0 {
+                window.out.push(self.alphabet.symbols[symbol].clone());
+                window.take -= 1;
+            } else {
+                stack.push((depth - 1, &self.alphabet.productions[symbol]));
+            }
+        }
+    }
+}
+
+/// The state of a [`GenerationIndex::slice`] query.
+struct Window<T> {
+    skip: u128,
+    take: u128,
+    out: Vec<T>,
+}
+
+fn sum(lengths: impl Iterator<Item = u128>) -> u128 {
+    lengths.fold(0, u128::saturating_add)
+}
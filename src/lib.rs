@@ -0,0 +1,70 @@
+//! Extensible implementations of an [L-System](https://en.wikipedia.org/wiki/L-system).
+//!
+//! An L-System consists of an alphabet, an axiom (the initial state of the system) and a set of
+//! rules that transform one string of elements into another. The alphabet is simply a common
+//! type `T`, the axiom a `Vec<T>` and each flavour of rule lives in its own module:
+//!
+//! - [`deterministic`]: one element is replaced by a sequence of elements
+//! - [`context`]: a slice of elements is replaced by a sequence of elements, optionally only
+//!   when surrounded by a given context
+//! - [`stochastic`]: like [`context`], but each rule only applies with a given chance
+//! - [`functional`]: the replacement is computed by an arbitrary function
+//! - [`parametric`]: elements are modules with numeric parameters, which rules bind, test and
+//!   compute the parameters of the replacement from, either with closures or with the
+//!   [`expression`] language
+//!
+//! Each module offers a free function that expands an axiom with rules of its own flavour, and a
+//! [`Rule`] implementation so rules of all flavours can be mixed in a single [`LSystem`].
+//!
+//! Grammars over `char` symbols, including parametric and stochastic ones, can also be written
+//! as text and parsed with [`grammar`], or imported from the files of cpfg with [`cpfg`].
+//!
+//! Context-free, deterministic L-Systems can also be queried without expanding them: [`index`]
+//! looks up elements of arbitrarily large generations, and [`analysis`] predicts their length
+//! and composition.
+//!
+//! Generations can be drawn with the turtle graphics of [`turtle`], and the drawings saved as
+//! images with [`svg`] or rasterised with [`raster`]. [`turtle3d`] draws them in three
+//! dimensions instead, and [`mesh`] turns such drawings into tubes to export as OBJ files.
+//!
+//! With the `parallel` feature enabled, large generations can be rewritten on several threads,
+//! see `LSystem::par_expand`.
+
+mod alphabet;
+pub mod analysis;
+pub mod automaton;
+pub mod branching;
+pub mod context;
+pub mod cpfg;
+pub mod deterministic;
+pub mod expression;
+mod format;
+pub mod functional;
+mod generations;
+pub mod grammar;
+pub mod index;
+pub mod limits;
+pub mod mesh;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod parametric;
+mod png;
+pub mod raster;
+pub mod rule;
+pub mod stochastic;
+mod stream;
+pub mod svg;
+mod system;
+mod trie;
+pub mod turtle;
+pub mod turtle3d;
+
+pub use context::complex_lsystem;
+pub use deterministic::lsystem;
+pub use functional::arbitrary_lsystem;
+pub use generations::Generations;
+pub use parametric::parametric_lsystem;
+pub use rule::{BoxedRule, Rule};
+pub use stochastic::random_lsystem;
+pub use stream::Stream;
+pub use system::LSystem;
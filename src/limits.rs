@@ -0,0 +1,234 @@
+//! Budgets that stop runaway expansions before they exhaust memory or time.
+
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::RngCore;
+
+use crate::rule::{self, Randomness, Rule};
+
+/// The limits an expansion has to stay within, by default there are none.
+///
+/// Limits are enforced by [`LSystem::try_expand`](crate::LSystem::try_expand), and by
+/// [`try_random_lsystem`](crate::stochastic::try_random_lsystem) and
+/// [`try_arbitrary_lsystem`](crate::functional::try_arbitrary_lsystem) for the free functions.
+///
+/// ```
+/// use std::time::Duration;
+/// use lsystem::deterministic::SymbolRule;
+/// use lsystem::limits::{Exceeded, Limits};
+/// use lsystem::LSystem;
+///
+/// let system = LSystem::new(vec!['A']).with_rule(SymbolRule::new('A', vec!['A', 'A']));
+/// let limits = Limits::new().max_symbols(1000).timeout(Duration::from_secs(10));
+/// let error = system.try_expand(64, &limits).unwrap_err();
+/// assert_eq!(error.exceeded, Exceeded::Symbols);
+/// assert_eq!(error.generation, 9);
+/// assert_eq!(error.last.len(), 512);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    max_symbols: Option<usize>,
+    max_bytes: Option<usize>,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// No generation may have more than `max` elements.
+    pub fn max_symbols(mut self, max: usize) -> Self {
+        self.max_symbols = Some(max);
+        self
+    }
+
+    /// The buffers of the generation being rewritten and the one being produced may not take up
+    /// more than `max` bytes together, counting `size_of::<T>()` per element they have room
+    /// for. The buffer of the new generation grows within this budget, and the expansion stops
+    /// when it is full while there is more to rewrite.
+    ///
+    /// A single production larger than the remaining budget is only detected after it was
+    /// written, and memory owned by the elements themselves, like the contents of a `String`, is
+    /// not counted.
+    pub fn max_bytes(mut self, max: usize) -> Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    /// The expansion has to be done by `deadline`.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// The expansion has to be done within `timeout` from now.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    /// The expansion stops as soon as `token` is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Checks a (partial) generation of `len` elements against all limits, where `allocated` is
+    /// the number of elements all buffers have room for.
+    /// The clock and the cancellation token are only consulted when `poll` is true, since they are
+    /// comparatively expensive to check after every element.
+    fn check<T>(&self, len: usize, allocated: usize, poll: bool) -> Result<(), Exceeded> {
+        if self.max_symbols.is_some_and(|max| len > max) {
+            return Err(Exceeded::Symbols);
+        }
+        if self
+            .max_bytes
+            .is_some_and(|max| allocated.saturating_mul(mem::size_of::<T>()) > max)
+        {
+            return Err(Exceeded::Bytes);
+        }
+        if poll {
+            if self.cancellation.as_ref().is_some_and(|c| c.is_cancelled()) {
+                return Err(Exceeded::Cancelled);
+            }
+            if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(Exceeded::Deadline);
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes room for at least one more element in a full `out`, growing it like a `Vec` would
+    /// but never beyond the byte budget, given that `other` elements are allocated elsewhere.
+    fn reserve<T>(&self, out: &mut Vec<T>, other: usize) -> Result<(), Exceeded> {
+        let size = mem::size_of::<T>();
+        let Some(max) = self.max_bytes.filter(|_| size > 0) else {
+            return Ok(());
+        };
+        if out.len() < out.capacity() {
+            return Ok(());
+        }
+        let room = (max / size).saturating_sub(other);
+        let grown = out.capacity().saturating_mul(2).max(4).min(room);
+        if grown <= out.len() {
+            return Err(Exceeded::Bytes);
+        }
+        out.reserve_exact(grown - out.len());
+        Ok(())
+    }
+}
+
+/// A flag that can be shared with another thread to cancel an expansion.
+/// Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The limit that stopped an expansion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exceeded {
+    Symbols,
+    Bytes,
+    Deadline,
+    Cancelled,
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Exceeded::Symbols => "maximum number of symbols exceeded",
+            Exceeded::Bytes => "maximum number of bytes exceeded",
+            Exceeded::Deadline => "deadline passed",
+            Exceeded::Cancelled => "cancelled",
+        })
+    }
+}
+
+/// An expansion that was stopped by one of its [`Limits`], with the last generation that was
+/// completed within them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionError<T> {
+    pub exceeded: Exceeded,
+    /// The number of the last completed generation.
+    pub generation: u32,
+    pub last: Vec<T>,
+}
+
+impl<T> fmt::Display for ExpansionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expansion stopped after generation {}: {}",
+            self.generation, self.exceeded
+        )
+    }
+}
+
+impl<T: fmt::Debug> Error for ExpansionError<T> {}
+
+/// How many elements are produced between two looks at the clock and the cancellation token.
+const POLL_INTERVAL: usize = 1024;
+
+/// Rewrites `axiom` the given number of times like [`rule::expand`], but stops as soon as a
+/// generation exceeds the limits.
+pub(crate) fn try_expand<T: Clone, R: Rule<T>>(
+    axiom: Vec<T>,
+    rules: &[R],
+    iterations: u32,
+    rng: &mut dyn RngCore,
+    limits: &Limits,
+) -> Result<Vec<T>, ExpansionError<T>> {
+    let mut current = axiom;
+    if let Err(exceeded) = limits.check::<T>(current.len(), current.capacity(), true) {
+        return Err(ExpansionError {
+            exceeded,
+            generation: 0,
+            last: current,
+        });
+    }
+    let mut next = Vec::new();
+    for generation in 0..iterations {
+        next.clear();
+        let mut steps = 0;
+        let mut randomness = Randomness::Shared(&mut *rng);
+        let other = current.capacity();
+        let rewritten = rule::rewrite_checked(&current, rules, &mut randomness, &mut next, |out| {
+            steps += 1;
+            limits.reserve(out, other)?;
+            limits.check::<T>(
+                out.len(),
+                other + out.capacity(),
+                steps % POLL_INTERVAL == 0,
+            )
+        })
+        .and_then(|()| limits.check::<T>(next.len(), other + next.capacity(), true));
+        if let Err(exceeded) = rewritten {
+            return Err(ExpansionError {
+                exceeded,
+                generation,
+                last: current,
+            });
+        }
+        mem::swap(&mut current, &mut next);
+    }
+    Ok(current)
+}
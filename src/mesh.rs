@@ -0,0 +1,522 @@
+//! Triangle meshes of 3D turtle drawings, where every branch is a tube, and their export as
+//! Wavefront OBJ files with a material library.
+
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_3, PI, TAU};
+use std::fmt::Write;
+
+use crate::format::number;
+use crate::raster::Color;
+use crate::turtle3d::{rotate_around, Segment, Vector};
+
+/// The number of decimal places of the coordinates in OBJ files.
+const PRECISION: usize = 6;
+
+/// How much wider than a branch the collar joining it to its parent is.
+const FLARE: f64 = 1.5;
+
+/// How far around its parent a collar reaches to either side, in radians.
+const MAX_WRAP: f64 = FRAC_PI_3;
+
+/// A named surface colour, written to the material library.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    /// The name, in which OBJ files cannot have whitespace, so it is written as `_` instead.
+    pub name: String,
+    /// The diffuse colour, whose alpha channel becomes the opacity.
+    pub color: Color,
+}
+
+impl Material {
+    pub fn new(name: impl Into<String>, color: Color) -> Self {
+        Material {
+            name: name.into(),
+            color,
+        }
+    }
+}
+
+/// A triangle, given by the indices of its vertices in counterclockwise order when seen from
+/// outside, and the index of its material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Face {
+    pub vertices: [usize; 3],
+    pub material: usize,
+}
+
+/// A triangle mesh, where every vertex has a position and a normal.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<Vector>,
+    pub normals: Vec<Vector>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<Material>,
+}
+
+impl Mesh {
+    /// Writes the mesh as an OBJ file, which refers to its materials in the file `library`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `library` contains whitespace, which would make it several file names.
+    pub fn to_obj(&self, library: &str) -> String {
+        assert!(
+            !library.contains(char::is_whitespace),
+            "the name of a material library cannot contain whitespace"
+        );
+        let mut obj = String::new();
+        let point = |v: Vector| [v.x, v.y, v.z].map(|c| number(c, PRECISION)).join(" ");
+        writeln!(obj, "mtllib {library}").unwrap();
+        for &position in &self.positions {
+            writeln!(obj, "v {}", point(position)).unwrap();
+        }
+        for &normal in &self.normals {
+            writeln!(obj, "vn {}", point(normal)).unwrap();
+        }
+        let mut material = None;
+        for face in &self.faces {
+            if material != Some(face.material) {
+                let name = &self.materials[face.material].name;
+                writeln!(obj, "usemtl {}", material_name(name)).unwrap();
+                material = Some(face.material);
+            }
+            // indices start at 1, and every vertex has the normal with the same index
+            let [a, b, c] = face.vertices.map(|i| i + 1);
+            writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}").unwrap();
+        }
+        obj
+    }
+
+    /// Writes the material library the OBJ file refers to.
+    pub fn to_mtl(&self) -> String {
+        let mut mtl = String::new();
+        for Material { name, color } in &self.materials {
+            let channel = |c: u8| number(f64::from(c) / 255.0, PRECISION);
+            writeln!(mtl, "newmtl {}", material_name(name)).unwrap();
+            writeln!(
+                mtl,
+                "Kd {} {} {}",
+                channel(color.r),
+                channel(color.g),
+                channel(color.b)
+            )
+            .unwrap();
+            if color.a < 255 {
+                writeln!(mtl, "d {}", channel(color.a)).unwrap();
+            }
+        }
+        mtl
+    }
+
+    fn vertex(&mut self, position: Vector, normal: Vector) -> usize {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.positions.len() - 1
+    }
+
+    fn material(&mut self, material: &Material) -> usize {
+        match self.materials.iter().position(|m| m == material) {
+            Some(index) => index,
+            None => {
+                self.materials.push(material.clone());
+                self.materials.len() - 1
+            }
+        }
+    }
+}
+
+/// Turns segments into a [`Mesh`] of generalised cylinders, tubes whose diameter is the width
+/// of the segments.
+///
+/// Segments that continue one another, with a turn of at most 90 degrees and the same material,
+/// form a single tube with smooth joints: the cross-section at a joint is shared by both
+/// segments and tilted halfway between them, and the radius changes gradually from one segment
+/// to the next. Where a branch starts, the straightest continuation stays part of the tube and
+/// the other segments start tubes of their own. Such a branch is joined to the tube it grows out
+/// of by a collar: a ring on the surface of the parent, wider than the branch and with the
+/// parent's normals, which narrows into the branch just outside that surface, covering the seam
+/// and blending the normals of one tube into those of the other. A branch whose first segment
+/// ends too close to its parent for a collar, like a short one leaving a much thicker tube at a
+/// shallow angle, starts on the axis of the parent instead, with the parent hiding its end. The
+/// ends of every tube are closed.
+///
+/// ```
+/// use lsystem::mesh::Tubes;
+/// use lsystem::turtle3d::Turtle;
+///
+/// let elements: Vec<char> = "F[&F]F".chars().collect();
+/// let mesh = Tubes::new().sides(6).mesh(&Turtle::new(1.0, 45.0).interpret(&elements));
+/// // a tube of two segments and one of a single segment, each with two caps
+/// assert_eq!(mesh.faces.len(), 2 * 2 * 6 + 2 * 6 + 4 * 6);
+/// assert!(mesh.to_obj("tree.mtl").starts_with("mtllib tree.mtl\n"));
+/// assert!(mesh.to_mtl().starts_with("newmtl branch\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tubes {
+    sides: usize,
+    material: Material,
+}
+
+impl Default for Tubes {
+    fn default() -> Self {
+        Tubes {
+            sides: 8,
+            material: Material::new("branch", Color::rgb(102, 77, 51)),
+        }
+    }
+}
+
+impl Tubes {
+    /// Tubes with 8 sides, of a brown material named `branch`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of vertices around each cross-section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sides` is less than 3.
+    pub fn sides(mut self, sides: usize) -> Self {
+        assert!(sides >= 3, "a tube needs at least 3 sides");
+        self.sides = sides;
+        self
+    }
+
+    /// The material of segments without one of their own.
+    pub fn material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Builds tubes around `segments`, all of the same material.
+    pub fn mesh(&self, segments: &[Segment]) -> Mesh {
+        self.mesh_styled(segments, |_, _| None)
+    }
+
+    /// Builds tubes around `segments`, where `style` can give each segment, identified by its
+    /// index, a material of its own instead of the default one. Segments without length are
+    /// left out.
+    pub fn mesh_styled(
+        &self,
+        segments: &[Segment],
+        style: impl Fn(usize, &Segment) -> Option<Material>,
+    ) -> Mesh {
+        let mut mesh = Mesh::default();
+        let materials: Vec<usize> = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let material = style(i, segment);
+                mesh.material(material.as_ref().unwrap_or(&self.material))
+            })
+            .collect();
+        let directions: Vec<Option<Vector>> = segments
+            .iter()
+            .map(|s| (s.end - s.start).normalized())
+            .collect();
+
+        let key = |v: Vector| [v.x, v.y, v.z].map(f64::to_bits);
+        let mut starting_at: HashMap<[u64; 3], Vec<usize>> = HashMap::new();
+        for (i, segment) in segments.iter().enumerate() {
+            starting_at.entry(key(segment.start)).or_default().push(i);
+        }
+        let mut used: Vec<bool> = directions.iter().map(Option::is_none).collect();
+        let mut chains = Vec::new();
+        for first in 0..segments.len() {
+            if used[first] {
+                continue;
+            }
+            used[first] = true;
+            let mut chain = vec![first];
+            // continue with the straightest segment that starts where the tube ends
+            loop {
+                let last = *chain.last().unwrap();
+                let direction = directions[last].unwrap();
+                let next = starting_at
+                    .get(&key(segments[last].end))
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|&i| !used[i] && materials[i] == materials[last])
+                    .map(|i| (i, direction.dot(directions[i].unwrap())))
+                    .filter(|&(_, straightness)| straightness >= 0.0)
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+                let Some((next, _)) = next else { break };
+                used[next] = true;
+                chain.push(next);
+            }
+            chains.push(chain);
+        }
+
+        let rings: Vec<Vec<Ring>> = chains
+            .iter()
+            .map(|chain| Ring::along(chain, segments, &directions))
+            .collect();
+        // the first cross-section through each point that a tube passes through, which other
+        // tubes starting there branch off from
+        let mut joints = HashMap::new();
+        for (tube, rings) in rings.iter().enumerate() {
+            for ring in &rings[1..rings.len() - 1] {
+                joints.entry(key(ring.center)).or_insert((tube, ring));
+            }
+        }
+        for (tube, chain) in chains.iter().enumerate() {
+            let parent = joints
+                .get(&key(rings[tube][0].center))
+                .filter(|&&(other, _)| other != tube)
+                .map(|&(_, ring)| ring);
+            self.tube(&mut mesh, chain, &rings[tube], parent, &materials);
+        }
+        mesh
+    }
+
+    /// Adds the tube around a chain of connected segments with the given cross-sections. A tube
+    /// branching off `parent` is joined to it by a collar, if its first segment leaves room for
+    /// one.
+    fn tube(
+        &self,
+        mesh: &mut Mesh,
+        chain: &[usize],
+        rings: &[Ring],
+        parent: Option<&Ring>,
+        materials: &[usize],
+    ) {
+        let length = (rings[1].center - rings[0].center).length();
+        let joint = parent.and_then(|parent| Joint::new(parent, &rings[0], length, self.sides));
+        let mut vertices: Vec<Vec<usize>> = Vec::with_capacity(rings.len() + 1);
+        let mut previous: Option<(Vector, Vector)> = None;
+        for (k, ring) in rings.iter().enumerate() {
+            let Ring {
+                center,
+                tangent,
+                radius,
+                outgoing,
+            } = *ring;
+            // carry the orientation of the previous cross-section along, so the tube doesn't
+            // twist
+            let reference = match previous {
+                None => perpendicular(tangent),
+                Some((previous_tangent, reference)) => {
+                    let axis = previous_tangent.cross(tangent);
+                    let reference = match axis.normalized() {
+                        Some(unit) => {
+                            let angle = axis.length().atan2(previous_tangent.dot(tangent));
+                            rotate_around(reference, unit, angle)
+                        }
+                        None => reference,
+                    };
+                    (reference - tangent * reference.dot(tangent))
+                        .normalized()
+                        .unwrap_or_else(|| perpendicular(tangent))
+                }
+            };
+            previous = Some((tangent, reference));
+
+            // stretch the tilted cross-section at a joint so the tube keeps its thickness
+            let bend = (outgoing - tangent * outgoing.dot(tangent)).normalized();
+            let stretch = 1.0 / outgoing.dot(tangent) - 1.0;
+            let other = tangent.cross(reference);
+            let normals: Vec<Vector> = (0..self.sides)
+                .map(|j| {
+                    let (sin, cos) = (TAU * j as f64 / self.sides as f64).sin_cos();
+                    reference * cos + other * sin
+                })
+                .collect();
+            let center = match (k, &joint) {
+                (0, Some(joint)) => {
+                    let collar = normals
+                        .iter()
+                        .map(|&normal| {
+                            let (position, normal) = joint.collar(normal * radius);
+                            mesh.vertex(position, normal)
+                        })
+                        .collect();
+                    vertices.push(collar);
+                    center + outgoing * joint.neck
+                }
+                _ => center,
+            };
+            let ring = normals
+                .into_iter()
+                .map(|normal| {
+                    let offset = match bend {
+                        Some(bend) => normal + bend * (normal.dot(bend) * stretch),
+                        None => normal,
+                    };
+                    mesh.vertex(center + offset * radius, normal)
+                })
+                .collect();
+            vertices.push(ring);
+        }
+
+        // the collar adds a ring before the one the first segment starts with
+        let skipped = joint.is_some() as usize;
+        for (k, pair) in vertices.windows(2).enumerate() {
+            let material = materials[chain[k.saturating_sub(skipped)]];
+            let (a, b) = (&pair[0], &pair[1]);
+            for j in 0..self.sides {
+                let next = (j + 1) % self.sides;
+                mesh.faces.push(Face {
+                    vertices: [a[j], a[next], b[next]],
+                    material,
+                });
+                mesh.faces.push(Face {
+                    vertices: [a[j], b[next], b[j]],
+                    material,
+                });
+            }
+        }
+        // the rings run counterclockwise around the direction of the tube, so the one at the
+        // start is reversed to face backwards; a collar is hidden inside the parent, but closes
+        // the tube all the same
+        let (first, last) = (&rings[0], &rings[rings.len() - 1]);
+        let start: Vec<usize> = vertices[0].iter().rev().copied().collect();
+        self.cap(mesh, &start, -first.outgoing, materials[chain[0]]);
+        let end = &vertices[vertices.len() - 1];
+        self.cap(mesh, end, last.outgoing, materials[chain[chain.len() - 1]]);
+    }
+
+    /// Closes the end of a tube with a flat cap facing `normal`, around which `ring` runs
+    /// counterclockwise.
+    fn cap(&self, mesh: &mut Mesh, ring: &[usize], normal: Vector, material: usize) {
+        let points: Vec<Vector> = ring.iter().map(|&i| mesh.positions[i]).collect();
+        let center =
+            points.iter().fold(Vector::default(), |sum, &p| sum + p) * (1.0 / points.len() as f64);
+        let center = mesh.vertex(center, normal);
+        let rim: Vec<usize> = points.iter().map(|&p| mesh.vertex(p, normal)).collect();
+        for j in 0..rim.len() {
+            let next = (j + 1) % rim.len();
+            mesh.faces.push(Face {
+                vertices: [center, rim[j], rim[next]],
+                material,
+            });
+        }
+    }
+}
+
+/// The cross-section of a tube where one of its segments starts, or where the last one ends.
+#[derive(Debug, Clone, Copy)]
+struct Ring {
+    center: Vector,
+    /// The direction the cross-section is perpendicular to, halfway between the segments
+    /// meeting at a joint.
+    tangent: Vector,
+    radius: f64,
+    /// The direction of the segment starting here, or of the last segment at the end.
+    outgoing: Vector,
+}
+
+impl Ring {
+    /// The cross-sections along a chain of connected segments.
+    fn along(chain: &[usize], segments: &[Segment], directions: &[Option<Vector>]) -> Vec<Ring> {
+        let direction = |k: usize| directions[chain[k]].unwrap();
+        let n = chain.len();
+        (0..=n)
+            .map(|k| {
+                let (center, segment, outgoing) = match k < n {
+                    true => (segments[chain[k]].start, &segments[chain[k]], direction(k)),
+                    false => (
+                        segments[chain[n - 1]].end,
+                        &segments[chain[n - 1]],
+                        direction(n - 1),
+                    ),
+                };
+                let tangent = match k {
+                    0 => outgoing,
+                    _ => (direction(k - 1) + outgoing)
+                        .normalized()
+                        .unwrap_or(outgoing),
+                };
+                Ring {
+                    center,
+                    tangent,
+                    radius: segment.width / 2.0,
+                    outgoing,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where a branch leaves the surface of the tube it grows out of, which it starts on the axis of.
+///
+/// The branch is joined to its parent by a collar, a ring on the parent's surface that is wider
+/// than the branch and has the parent's normals, and starts its own surface at the neck, a little
+/// outside the parent, so that the faces in between blend one surface into the other.
+struct Joint<'a> {
+    parent: &'a Ring,
+    direction: Vector,
+    /// The parent's normal where the axis of the branch leaves it.
+    normal: Vector,
+    /// The direction around the parent there.
+    around: Vector,
+    /// The sine of the angle between the branch and its parent.
+    sin: f64,
+    /// The radius the collar lies at, within the faces of the parent so its rim is covered.
+    radius: f64,
+    /// How far along the branch its axis leaves the parent.
+    exit: f64,
+    /// How far along the branch its surface starts.
+    neck: f64,
+}
+
+impl<'a> Joint<'a> {
+    /// The joint of the branch starting with `ring` and a segment of the given `length`, unless
+    /// that segment ends too close to the parent for a collar, or runs along its axis.
+    fn new(parent: &'a Ring, ring: &Ring, length: f64, sides: usize) -> Option<Self> {
+        let direction = ring.outgoing;
+        let across = direction - parent.tangent * direction.dot(parent.tangent);
+        let sin = across.length();
+        if sin < 1e-6 {
+            return None;
+        }
+        let cos = (1.0 - sin * sin).max(0.0).sqrt();
+        let radius = parent.radius * (PI / sides as f64).cos();
+        let exit = radius / sin;
+        // the collar reaches at most FLARE * radius * cos / sin further along the branch, so
+        // starting the branch another radius beyond keeps the faces in between from folding
+        let neck = exit + FLARE * ring.radius * cos / sin + ring.radius;
+        // a tilted cross-section at the end of the segment reaches back by up to the radius
+        (neck + ring.radius < length).then(|| Joint {
+            parent,
+            direction,
+            normal: across * (1.0 / sin),
+            around: parent.tangent.cross(across * (1.0 / sin)),
+            sin,
+            radius,
+            exit,
+            neck,
+        })
+    }
+
+    /// The point of the collar around `offset` from the axis of the branch, and the normal of
+    /// the parent there.
+    fn collar(&self, offset: Vector) -> (Vector, Vector) {
+        // along the branch onto the plane touching the parent where the branch leaves it, then
+        // wrapped around the parent
+        let offset = offset * FLARE;
+        let flat = offset - self.direction * (offset.dot(self.normal) / self.sin);
+        let angle = (flat.dot(self.around) / self.radius).clamp(-MAX_WRAP, MAX_WRAP);
+        let normal = self.normal * angle.cos() + self.around * angle.sin();
+        let exit = self.parent.center + self.direction * self.exit;
+        let height = (exit - self.parent.center + flat).dot(self.parent.tangent);
+        let position = self.parent.center + self.parent.tangent * height + normal * self.radius;
+        (position, normal)
+    }
+}
+
+/// The `name` of a material as written to OBJ and MTL files.
+fn material_name(name: &str) -> String {
+    name.replace(char::is_whitespace, "_")
+}
+
+/// Some unit vector perpendicular to the unit vector `v`.
+fn perpendicular(v: Vector) -> Vector {
+    let axis = if v.x.abs() < 0.9 {
+        Vector::X
+    } else {
+        Vector::Y
+    };
+    v.cross(axis).normalized().unwrap()
+}
@@ -0,0 +1,192 @@
+//! Rewriting large generations on several threads.
+
+use std::thread;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::rule::{self, Phase, Randomness, Rule};
+
+/// Generations shorter than this are not worth splitting up.
+const MIN_CHUNK: usize = 4096;
+
+/// How many chunks each available thread gets, to even out chunks that take longer than others.
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// The randomness a single thread uses for its chunk, derived from the [`Randomness`] of the
+/// whole generation.
+#[derive(Clone, Copy)]
+enum ChunkRandomness {
+    /// A generator seeded with the given number, drawn from in order.
+    Seeded(u64),
+    /// The same positional randomness as the whole generation.
+    Positional { seed: u64, generation: u64 },
+}
+
+impl ChunkRandomness {
+    fn split(randomness: &mut Randomness) -> Self {
+        match randomness {
+            Randomness::Shared(rng) => ChunkRandomness::Seeded(rng.next_u64()),
+            Randomness::Positional { seed, generation } => ChunkRandomness::Positional {
+                seed: *seed,
+                generation: *generation,
+            },
+        }
+    }
+
+    /// Calls `f` with the [`Randomness`] for this chunk.
+    fn with<U>(self, f: impl FnOnce(&mut Randomness) -> U) -> U {
+        match self {
+            ChunkRandomness::Seeded(seed) => f(&mut Randomness::Shared(
+                &mut ChaCha8Rng::seed_from_u64(seed),
+            )),
+            ChunkRandomness::Positional { seed, generation } => {
+                f(&mut Randomness::Positional { seed, generation })
+            }
+        }
+    }
+}
+
+/// Rewrites `input` once like [`rule::rewrite`], splitting the work across threads.
+///
+/// Rewriting happens in three phases:
+/// 1. in parallel, the first matching rule is determined for every position of the input
+/// 2. sequentially, the positions at which a match actually starts are found by jumping from one
+///    match to the position after it, exactly as [`rule::rewrite`] walks the input
+/// 3. in parallel, the replacements are produced for chunks of the input that begin at one of
+///    those positions, and concatenated in order
+///
+/// Since the second phase decides which matches apply, patterns that straddle the boundaries of
+/// the chunks of the first phase are handled exactly as they are sequentially, and deterministic
+/// rules produce identical output. With [`Randomness::Positional`] so do stochastic rules; with
+/// [`Randomness::Shared`] each chunk draws its randomness from a generator seeded by the shared
+/// one, so stochastic rules produce a different result than they do sequentially.
+///
+/// The first phase needs a table with an entry for every element of the input in addition to
+/// the two generations.
+pub(crate) fn rewrite<T, R>(input: &[T], rules: &[R], randomness: &mut Randomness, out: &mut Vec<T>)
+where
+    T: Clone + Send + Sync,
+    R: Rule<T> + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = input
+        .len()
+        .div_ceil(threads * CHUNKS_PER_THREAD)
+        .max(MIN_CHUNK);
+    if input.len() <= chunk {
+        return rule::rewrite(input, rules, randomness, out);
+    }
+
+    let mut matches = vec![None; input.len()];
+    thread::scope(|scope| {
+        for (index, matches) in matches.chunks_mut(chunk).enumerate() {
+            let chunk_randomness = ChunkRandomness::split(randomness);
+            scope.spawn(move || {
+                chunk_randomness.with(|randomness| {
+                    let offset = index * chunk;
+                    for (i, found) in matches.iter_mut().enumerate() {
+                        let at = offset + i;
+                        *found = randomness.at(at, Phase::Match, |rng| {
+                            rule::first_match(input, at, rules, rng)
+                        });
+                    }
+                })
+            });
+        }
+    });
+
+    // only keep the matches at positions the sequential walk would visit, and split the input
+    // into chunks at the first visited position after every multiple of `chunk`
+    let mut starts = Vec::with_capacity(input.len() / chunk + 1);
+    let mut i = 0;
+    while i < input.len() {
+        if i >= starts.len() * chunk {
+            starts.push(i);
+        }
+        let len = matches[i].map_or(1, |(_, len)| len);
+        matches[i + 1..(i + len).min(input.len())].fill(None);
+        i += len;
+    }
+    starts.push(input.len());
+
+    let outputs: Vec<Vec<T>> = thread::scope(|scope| {
+        let handles: Vec<_> = starts
+            .windows(2)
+            .map(|bounds| {
+                let (start, end) = (bounds[0], bounds[1]);
+                let matches = &matches;
+                let chunk_randomness = ChunkRandomness::split(randomness);
+                scope.spawn(move || {
+                    let mut out = Vec::with_capacity((end - start) * 2);
+                    chunk_randomness.with(|randomness| {
+                        let mut i = start;
+                        while i < end {
+                            match matches[i] {
+                                Some((rule, len)) => {
+                                    randomness.at(i, Phase::Produce, |rng| {
+                                        rules[rule].produce(input, i..i + len, rng, &mut out)
+                                    });
+                                    i += len;
+                                }
+                                None => {
+                                    out.push(input[i].clone());
+                                    i += 1;
+                                }
+                            }
+                        }
+                    });
+                    out
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    out.reserve(outputs.iter().map(Vec::len).sum());
+    for mut output in outputs {
+        out.append(&mut output);
+    }
+}
+
+/// Rewrites `axiom` the given number of times like [`rule::expand`], splitting the work of each
+/// generation across threads.
+pub(crate) fn expand<T, R>(
+    axiom: Vec<T>,
+    rules: &[R],
+    iterations: u32,
+    rng: &mut dyn RngCore,
+) -> Vec<T>
+where
+    T: Clone + Send + Sync,
+    R: Rule<T> + Sync,
+{
+    let mut randomness = Randomness::Shared(rng);
+    rule::iterate(axiom, iterations, |input, _, out| {
+        rewrite(input, rules, &mut randomness, out)
+    })
+}
+
+/// Like [`rule::expand_positional`], splitting the work of each generation across threads.
+pub(crate) fn expand_positional<T, R>(
+    axiom: Vec<T>,
+    rules: &[R],
+    iterations: u32,
+    seed: u64,
+) -> Vec<T>
+where
+    T: Clone + Send + Sync,
+    R: Rule<T> + Sync,
+{
+    rule::iterate(axiom, iterations, |input, generation, out| {
+        let generation = u64::from(generation);
+        rewrite(
+            input,
+            rules,
+            &mut Randomness::Positional { seed, generation },
+            out,
+        )
+    })
+}
@@ -0,0 +1,396 @@
+//! Parametric L-Systems, whose elements are modules carrying numeric parameters.
+
+use std::fmt;
+use std::ops::{Index, Range};
+
+use rand::RngCore;
+
+use crate::branching::Branching;
+use crate::rule::{self, Rule};
+use crate::stochastic::{self, WeightError};
+
+/// An element of a parametric L-System: a symbol and its actual parameters, written `A(1,2)` in
+/// The Algorithmic Beauty of Plants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module<S> {
+    pub symbol: S,
+    pub params: Vec<f64>,
+}
+
+impl<S> Module<S> {
+    pub fn new(symbol: S, params: Vec<f64>) -> Self {
+        Module { symbol, params }
+    }
+
+    /// A module without parameters.
+    pub fn plain(symbol: S) -> Self {
+        Module::new(symbol, Vec::new())
+    }
+}
+
+impl<S: fmt::Display> fmt::Display for Module<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol)?;
+        if let Some((first, rest)) = self.params.split_first() {
+            write!(f, "({first}")?;
+            for param in rest {
+                write!(f, ",{param}")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// The values bound to the formal parameters of a rule while it is matched and applied.
+#[derive(Debug, Clone, Default)]
+pub struct Env<'a> {
+    bindings: Vec<(&'a str, f64)>,
+}
+
+impl<'a> Env<'a> {
+    pub fn new() -> Self {
+        Env::default()
+    }
+
+    /// Binds `name` to `value`, shadowing any earlier binding of the same name.
+    pub fn bind(&mut self, name: &'a str, value: f64) {
+        self.bindings.push((name, value));
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(bound, _)| *bound == name)
+            .map(|&(_, value)| value)
+    }
+}
+
+impl Index<&str> for Env<'_> {
+    type Output = f64;
+
+    /// Panics if `name` is not bound.
+    fn index(&self, name: &str) -> &f64 {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(bound, _)| *bound == name)
+            .map(|(_, value)| value)
+            .unwrap_or_else(|| panic!("unbound parameter `{name}`"))
+    }
+}
+
+/// An arithmetic expression computing an actual parameter of a successor module.
+///
+/// Implemented for closures taking the [`Env`], and for constants.
+pub trait Expression {
+    fn evaluate(&self, env: &Env, rng: &mut dyn RngCore) -> f64;
+}
+
+impl<F: Fn(&Env) -> f64> Expression for F {
+    fn evaluate(&self, env: &Env, _: &mut dyn RngCore) -> f64 {
+        self(env)
+    }
+}
+
+impl Expression for f64 {
+    fn evaluate(&self, _: &Env, _: &mut dyn RngCore) -> f64 {
+        *self
+    }
+}
+
+/// A guard that has to hold for a parametric rule to match.
+///
+/// Implemented for closures taking the [`Env`].
+pub trait Condition {
+    fn holds(&self, env: &Env, rng: &mut dyn RngCore) -> bool;
+}
+
+impl<F: Fn(&Env) -> bool> Condition for F {
+    fn holds(&self, env: &Env, _: &mut dyn RngCore) -> bool {
+        self(env)
+    }
+}
+
+/// A module in the predecessor or context of a rule, naming its formal parameters.
+/// It matches modules with the same symbol and the same number of parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<S> {
+    pub symbol: S,
+    pub params: Vec<String>,
+}
+
+impl<S: PartialEq> Pattern<S> {
+    pub fn new(symbol: S, params: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Pattern {
+            symbol,
+            params: params.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// A pattern matching modules without parameters.
+    pub fn plain(symbol: S) -> Self {
+        Pattern {
+            symbol,
+            params: Vec::new(),
+        }
+    }
+
+    /// Binds the formal parameters to those of `module`, if it matches.
+    fn bind<'a>(&'a self, module: &Module<S>, env: &mut Env<'a>) -> bool {
+        if self.symbol != module.symbol || self.params.len() != module.params.len() {
+            return false;
+        }
+        for (name, &value) in self.params.iter().zip(&module.params) {
+            env.bind(name, value);
+        }
+        true
+    }
+}
+
+/// A module in the successor of a rule, computing its actual parameters from the [`Env`].
+pub struct Template<S> {
+    pub symbol: S,
+    pub params: Vec<Box<dyn Expression + Send + Sync>>,
+}
+
+impl<S> Template<S> {
+    /// A template producing a module without parameters, see [`Template::param`] to add some.
+    pub fn new(symbol: S) -> Self {
+        Template {
+            symbol,
+            params: Vec::new(),
+        }
+    }
+
+    /// Appends a parameter computed by `expression`.
+    pub fn param(mut self, expression: impl Expression + Send + Sync + 'static) -> Self {
+        self.params.push(Box::new(expression));
+        self
+    }
+}
+
+impl<S: Clone> Template<S> {
+    fn instantiate(&self, env: &Env, rng: &mut dyn RngCore) -> Module<S> {
+        Module::new(
+            self.symbol.clone(),
+            self.params.iter().map(|e| e.evaluate(env, rng)).collect(),
+        )
+    }
+}
+
+/// A parametric rule, written `left < predecessor > right : condition -> successor` in The
+/// Algorithmic Beauty of Plants.
+///
+/// The rule matches a single module with the symbol and number of parameters of the predecessor,
+/// surrounded by the context, if any. The formal parameters of the predecessor and the context are
+/// bound to the actual parameters of the matched modules, then the condition is evaluated, and if
+/// it holds the successor is produced with parameters computed from the bound values.
+/// A [stochastic](ParametricRule::stochastic) rule chooses one of several successors at random.
+///
+/// ```
+/// use lsystem::parametric::{Env, Module, ParametricRule, Pattern, Template};
+/// use lsystem::LSystem;
+///
+/// // A(t) : t > 3 -> F(t*0.5)[+A(0)]A(t+1)
+/// let branch = ParametricRule::new(
+///     Pattern::new('A', ["t"]),
+///     vec![
+///         Template::new('F').param(|env: &Env| env["t"] * 0.5),
+///         Template::new('['),
+///         Template::new('+'),
+///         Template::new('A').param(0.0),
+///         Template::new(']'),
+///         Template::new('A').param(|env: &Env| env["t"] + 1.0),
+///     ],
+/// )
+/// .with_condition(|env: &Env| env["t"] > 3.0);
+/// // A(t) : t <= 3 -> A(t+1)
+/// let grow = ParametricRule::new(
+///     Pattern::new('A', ["t"]),
+///     vec![Template::new('A').param(|env: &Env| env["t"] + 1.0)],
+/// );
+///
+/// let system = LSystem::new(vec![Module::new('A', vec![3.0])])
+///     .with_rule(branch)
+///     .with_rule(grow);
+/// let generation: String = system.expand(2).iter().map(|m| m.to_string()).collect();
+/// assert_eq!(generation, "F(2)[+A(0)]A(5)");
+/// ```
+pub struct ParametricRule<S> {
+    pub left: Vec<Pattern<S>>,
+    pub predecessor: Pattern<S>,
+    pub right: Vec<Pattern<S>>,
+    pub condition: Option<Box<dyn Condition + Send + Sync>>,
+    pub branching: Option<Branching<S>>,
+    successors: Vec<Vec<Template<S>>>,
+    /// The cumulative, normalised probabilities of the successors, the last one is exactly 1.0.
+    thresholds: Vec<f64>,
+}
+
+impl<S: PartialEq> ParametricRule<S> {
+    /// A context-free rule without a condition.
+    pub fn new(predecessor: Pattern<S>, successor: Vec<Template<S>>) -> Self {
+        ParametricRule {
+            left: Vec::new(),
+            predecessor,
+            right: Vec::new(),
+            condition: None,
+            branching: None,
+            successors: vec![successor],
+            thresholds: vec![1.0],
+        }
+    }
+
+    /// A context-free rule without a condition, producing one of the `successors` with a
+    /// probability proportional to its weight.
+    /// Fails for the same weights as a [`WeightedRule`](crate::stochastic::WeightedRule).
+    pub fn stochastic(
+        predecessor: Pattern<S>,
+        successors: Vec<(Vec<Template<S>>, f64)>,
+    ) -> Result<Self, WeightError> {
+        let weights: Vec<f64> = successors.iter().map(|(_, w)| *w).collect();
+        let thresholds = stochastic::thresholds(&weights)?;
+        Ok(ParametricRule {
+            successors: successors.into_iter().map(|(s, _)| s).collect(),
+            thresholds,
+            ..ParametricRule::new(predecessor, Vec::new())
+        })
+    }
+
+    /// The successors with their normalised probabilities, which add up to 1.0.
+    pub fn successors(&self) -> impl Iterator<Item = (&[Template<S>], f64)> {
+        let previous = std::iter::once(0.0).chain(self.thresholds.iter().copied());
+        self.successors
+            .iter()
+            .zip(self.thresholds.iter().zip(previous))
+            .map(|(successor, (t, p))| (successor.as_slice(), t - p))
+    }
+
+    /// Only matches when the predecessor is preceded by `left`.
+    pub fn with_left(mut self, left: Vec<Pattern<S>>) -> Self {
+        self.left = left;
+        self
+    }
+
+    /// Only matches when the predecessor is followed by `right`.
+    pub fn with_right(mut self, right: Vec<Pattern<S>>) -> Self {
+        self.right = right;
+        self
+    }
+
+    /// Only matches when `condition` holds for the bound parameters.
+    pub fn with_condition(mut self, condition: impl Condition + Send + Sync + 'static) -> Self {
+        self.condition = Some(Box::new(condition));
+        self
+    }
+
+    /// Matches the context along the branches described by `branching`, see [`Branching`].
+    pub fn with_branching(mut self, branching: Branching<S>) -> Self {
+        self.branching = Some(branching);
+        self
+    }
+
+    /// Binds the formal parameters of the predecessor and the context to the actual parameters of
+    /// the modules around `input[at]`, if they all match.
+    fn bind(&self, input: &[Module<S>], at: usize) -> Option<Env<'_>> {
+        let mut env = Env::new();
+        if !self.predecessor.bind(&input[at], &mut env) {
+            return None;
+        }
+        let matched = match &self.branching {
+            None => {
+                let start = at.checked_sub(self.left.len())?;
+                let end = at + 1 + self.right.len();
+                end <= input.len()
+                    && self
+                        .left
+                        .iter()
+                        .zip(&input[start..at])
+                        .chain(self.right.iter().zip(&input[at + 1..end]))
+                        .all(|(pattern, module)| pattern.bind(module, &mut env))
+            }
+            Some(branching) => {
+                branching.left_context_matches(
+                    input,
+                    at,
+                    &self.left,
+                    |m| &m.symbol,
+                    |pattern, module| pattern.bind(module, &mut env),
+                ) && branching.right_context_matches(
+                    input,
+                    at + 1,
+                    &self.right,
+                    |m| &m.symbol,
+                    |p| &p.symbol,
+                    |pattern, module| pattern.bind(module, &mut env),
+                )
+            }
+        };
+        matched.then_some(env)
+    }
+}
+
+impl<S: PartialEq + Clone> Rule<Module<S>> for ParametricRule<S> {
+    fn matches(&self, input: &[Module<S>], at: usize, rng: &mut dyn RngCore) -> Option<usize> {
+        let env = self.bind(input, at)?;
+        self.condition
+            .as_ref()
+            .is_none_or(|condition| condition.holds(&env, rng))
+            .then_some(1)
+    }
+
+    fn produce(
+        &self,
+        input: &[Module<S>],
+        matched: Range<usize>,
+        rng: &mut dyn RngCore,
+        out: &mut Vec<Module<S>>,
+    ) {
+        let env = self
+            .bind(input, matched.start)
+            .expect("only called after a match");
+        let successor = match self.successors.len() {
+            1 => &self.successors[0],
+            _ => &self.successors[stochastic::choose(&self.thresholds, rng)],
+        };
+        out.extend(successor.iter().map(|t| t.instantiate(&env, rng)));
+    }
+
+    fn span(&self) -> Option<usize> {
+        match self.branching {
+            None => Some(1 + self.right.len()),
+            // branches can be arbitrarily long
+            Some(_) => None,
+        }
+    }
+
+    fn lookbehind(&self) -> usize {
+        self.left.len()
+    }
+}
+
+/// Expands a parametric L-System, where the first rule matching a module wins.
+///
+/// ```
+/// use lsystem::parametric::{parametric_lsystem, Env, Module, ParametricRule, Pattern, Template};
+///
+/// // F(x) -> F(x/3)F(x/3)
+/// let rules = [ParametricRule::new(
+///     Pattern::new('F', ["x"]),
+///     vec![
+///         Template::new('F').param(|env: &Env| env["x"] / 3.0),
+///         Template::new('F').param(|env: &Env| env["x"] / 3.0),
+///     ],
+/// )];
+/// let generation = parametric_lsystem(vec![Module::new('F', vec![9.0])], &rules, 2);
+/// assert_eq!(generation, vec![Module::new('F', vec![1.0]); 4]);
+/// ```
+pub fn parametric_lsystem<S: PartialEq + Clone>(
+    axiom: Vec<Module<S>>,
+    rules: &[ParametricRule<S>],
+    iterations: u32,
+) -> Vec<Module<S>> {
+    rule::expand(axiom, rules, iterations, &mut rand::thread_rng())
+}
@@ -0,0 +1,112 @@
+//! A minimal PNG encoder, storing the image data without compression so it needs no
+//! dependencies.
+
+/// The largest amount of data a stored deflate block can hold.
+const MAX_STORED: usize = 65535;
+
+/// The amount of image data per IDAT chunk. PNG limits chunks to 2^31 - 1 bytes, and decoders
+/// read smaller ones more easily.
+const MAX_IDAT: usize = 1 << 20;
+
+/// Encodes an image of 8 bit RGBA pixels, given row by row, as a PNG file.
+pub(crate) fn encode(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    // PNG does not allow empty images
+    debug_assert!(width > 0 && height > 0);
+    debug_assert_eq!(rgba.len(), width as usize * height as usize * 4);
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+
+    // every row starts with its filter type, which is always 0 (none)
+    let row = width as usize * 4;
+    let mut scanlines = Vec::with_capacity((row + 1) * height as usize);
+    for pixels in rgba.chunks(row.max(1)).take(height as usize) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(pixels);
+    }
+    // the zlib stream may be split anywhere, decoders join the data of consecutive IDAT chunks
+    for data in zlib_stored(&scanlines).chunks(MAX_IDAT) {
+        chunk(&mut png, b"IDAT", data);
+    }
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Appends a chunk with its length and checksum.
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED).max(1);
+    let mut zlib = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // deflate with a 32K window, no preset dictionary, and a valid header check
+    zlib.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(MAX_STORED).peekable();
+    if chunks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+/// The CRC-32 used by PNG (and zip), with the reflected polynomial 0xedb88320.
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut n = 0;
+        while n < 256 {
+            let mut c = n as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 == 1 {
+                    0xedb88320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[n] = c;
+            n += 1;
+        }
+        table
+    };
+    !data.iter().fold(!0u32, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// The checksum at the end of a zlib stream.
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // the sums cannot overflow within 5552 bytes, so the modulo is only needed once per chunk
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,276 @@
+//! A software rasteriser for turtle drawings, writing PNG and PPM images without any
+//! dependencies.
+
+use crate::png;
+use crate::turtle::{bounds, Point, Segment};
+
+/// A colour with straight (not premultiplied) alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+}
+
+/// The colour of a line and its width in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pen {
+    pub color: Color,
+    pub width: f64,
+}
+
+impl Pen {
+    pub fn new(color: Color, width: f64) -> Self {
+        Pen { color, width }
+    }
+}
+
+/// An RGBA image, stored row by row from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Image {
+    /// An image filled with `background`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is 0, since image formats need at least one pixel.
+    pub fn new(width: u32, height: u32, background: Color) -> Self {
+        assert!(width > 0 && height > 0, "an image needs at least one pixel");
+        let pixels = width as usize * height as usize;
+        let Color { r, g, b, a } = background;
+        Image {
+            width,
+            height,
+            rgba: [r, g, b, a].repeat(pixels),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The colour of the pixel in column `x` and row `y`, counting from the top left.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = self.index(x, y);
+        let [r, g, b, a] = self.rgba[i..i + 4] else {
+            unreachable!()
+        };
+        Color { r, g, b, a }
+    }
+
+    /// The channels of all pixels, four bytes per pixel, row by row.
+    pub fn as_rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    /// Encodes the image as PNG. The image data is stored without compression.
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.rgba)
+    }
+
+    /// Encodes the image as binary PPM, which has no alpha channel, so it is dropped.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(self.rgba.chunks(4).flat_map(|pixel| &pixel[..3]));
+        ppm
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) is outside the image"
+        );
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    /// Paints `color` over the pixel, covering the given fraction of it.
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: f64) {
+        let i = self.index(x, y);
+        let alpha = f64::from(color.a) / 255.0 * coverage.clamp(0.0, 1.0);
+        let below = f64::from(self.rgba[i + 3]) / 255.0;
+        let out = alpha + below * (1.0 - alpha);
+        if out <= 0.0 {
+            return;
+        }
+        for (channel, src) in [color.r, color.g, color.b].into_iter().enumerate() {
+            let dst = f64::from(self.rgba[i + channel]);
+            let blended = (f64::from(src) * alpha + dst * below * (1.0 - alpha)) / out;
+            self.rgba[i + channel] = blended.round() as u8;
+        }
+        self.rgba[i + 3] = (out * 255.0).round() as u8;
+    }
+
+    /// Draws an anti-aliased line with round ends between two points in pixel coordinates.
+    fn line(&mut self, from: Point, to: Point, pen: Pen) {
+        let radius = pen.width.max(0.0) / 2.0;
+        // lines thinner than a pixel are drawn a pixel wide, but fainter
+        let (reach, faintness) = (radius.max(0.5), pen.width.min(1.0));
+        let x_range = pixel_range(
+            from.x.min(to.x) - reach,
+            from.x.max(to.x) + reach,
+            self.width,
+        );
+        let y_range = pixel_range(
+            from.y.min(to.y) - reach,
+            from.y.max(to.y) + reach,
+            self.height,
+        );
+        for y in y_range {
+            for x in x_range.clone() {
+                let center = Point::new(f64::from(x) + 0.5, f64::from(y) + 0.5);
+                let coverage = reach + 0.5 - distance(center, from, to);
+                if coverage > 0.0 {
+                    self.blend(x, y, pen.color, coverage.min(1.0) * faintness);
+                }
+            }
+        }
+    }
+}
+
+/// The pixels whose centers might lie between `low` and `high`, within an image of size `size`.
+fn pixel_range(low: f64, high: f64, size: u32) -> std::ops::Range<u32> {
+    let start = (low - 0.5).floor().max(0.0) as u32;
+    let end = ((high + 0.5).ceil().max(0.0) as u32).min(size);
+    start.min(end)..end
+}
+
+/// The distance from `p` to the segment between `a` and `b`.
+fn distance(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (cx, cy) = (a.x + t * dx, a.y + t * dy);
+    ((p.x - cx).powi(2) + (p.y - cy).powi(2)).sqrt()
+}
+
+/// Renders segments into an [`Image`] of a fixed size, scaling the drawing to fit within the
+/// margins and centering it.
+///
+/// ```
+/// use lsystem::raster::{Color, Pen, Raster};
+/// use lsystem::turtle::Turtle;
+///
+/// let square: Vec<char> = "F+F+F+F".chars().collect();
+/// let image = Raster::new(64, 64)
+///     .margin(8)
+///     .pen(Pen::new(Color::BLACK, 2.0))
+///     .render(&Turtle::new(1.0, 90.0).interpret(&square));
+/// assert_eq!(image.pixel(8, 32), Color::BLACK);
+/// assert_eq!(image.pixel(32, 32), Color::WHITE);
+/// assert!(image.to_png().starts_with(b"\x89PNG"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Raster {
+    width: u32,
+    height: u32,
+    margin: u32,
+    background: Color,
+    pen: Pen,
+}
+
+impl Raster {
+    /// Black lines one pixel wide on white, with a margin of 4 pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is 0, since image formats need at least one pixel.
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(width > 0 && height > 0, "an image needs at least one pixel");
+        Raster {
+            width,
+            height,
+            margin: 4,
+            background: Color::WHITE,
+            pen: Pen::new(Color::BLACK, 1.0),
+        }
+    }
+
+    /// The space around the drawing, in pixels. The ends of lines reach into it by half the width
+    /// of their pen.
+    pub fn margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// The pen of segments without one of their own.
+    pub fn pen(mut self, pen: Pen) -> Self {
+        self.pen = pen;
+        self
+    }
+
+    /// Renders `segments`, all with the same pen.
+    pub fn render(&self, segments: &[Segment]) -> Image {
+        self.render_styled(segments, |_, _| None)
+    }
+
+    /// Renders `segments`, where `style` can give each segment, identified by its index, a pen of
+    /// its own instead of the default one. Segments are painted in order, so later ones cover
+    /// earlier ones.
+    pub fn render_styled(
+        &self,
+        segments: &[Segment],
+        style: impl Fn(usize, &Segment) -> Option<Pen>,
+    ) -> Image {
+        let mut image = Image::new(self.width, self.height, self.background);
+        let Some((min, max)) = bounds(segments) else {
+            return image;
+        };
+        let available = |size: u32| f64::from(size.saturating_sub(self.margin.saturating_mul(2)));
+        let (available_x, available_y) = (available(self.width), available(self.height));
+        let (extent_x, extent_y) = (max.x - min.x, max.y - min.y);
+        let scale = [(available_x, extent_x), (available_y, extent_y)]
+            .into_iter()
+            .filter(|&(_, extent)| extent > 0.0)
+            .map(|(available, extent)| available / extent)
+            .fold(f64::INFINITY, f64::min);
+        let scale = if scale.is_finite() { scale } else { 1.0 };
+        let offset_x = f64::from(self.margin) + (available_x - extent_x * scale) / 2.0;
+        let offset_y = f64::from(self.margin) + (available_y - extent_y * scale) / 2.0;
+        // the y axis of the drawing points up, the one of the image down
+        let to_pixels = |p: Point| {
+            Point::new(
+                offset_x + (p.x - min.x) * scale,
+                offset_y + (max.y - p.y) * scale,
+            )
+        };
+
+        for (i, segment) in segments.iter().enumerate() {
+            let pen = style(i, segment).unwrap_or(self.pen);
+            image.line(to_pixels(segment.start), to_pixels(segment.end), pen);
+        }
+        image
+    }
+}
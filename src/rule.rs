@@ -0,0 +1,204 @@
+//! The [`Rule`] trait shared by all flavours of L-Systems, and the rewriting engine driving them.
+
+use std::convert::Infallible;
+use std::mem;
+use std::ops::Range;
+
+use rand::RngCore;
+
+use crate::stochastic::CounterRng;
+
+/// A production of an L-System: a pattern that is matched against the current generation, and
+/// the replacement that is produced for every match.
+///
+/// Rules receive the whole generation and the position to match at, so they are free to inspect
+/// elements around the match. Any randomness must be drawn from the `rng` that is passed in, so the
+/// caller stays in control of seeding.
+pub trait Rule<T> {
+    /// Tries to match this rule at `input[at..]`, returning the number of elements it consumes.
+    /// A rule that matches must consume at least one element, `Some(0)` is treated as no match.
+    fn matches(&self, input: &[T], at: usize, rng: &mut dyn RngCore) -> Option<usize>;
+
+    /// Appends the replacement for `input[matched]` to `out`.
+    /// Only called with a range that this rule has just matched.
+    fn produce(&self, input: &[T], matched: Range<usize>, rng: &mut dyn RngCore, out: &mut Vec<T>);
+
+    /// The number of elements from the match position onwards that [`Rule::matches`] inspects at
+    /// most, or `None` if it may look anywhere in the generation.
+    /// Only rules with a known span can be expanded lazily by a [`Stream`](crate::Stream).
+    fn span(&self) -> Option<usize> {
+        None
+    }
+
+    /// The number of elements before the match position that the rule inspects at most.
+    /// Only relevant for rules with a known [`span`](Rule::span).
+    fn lookbehind(&self) -> usize {
+        0
+    }
+}
+
+impl<T, R: Rule<T> + ?Sized> Rule<T> for Box<R> {
+    fn matches(&self, input: &[T], at: usize, rng: &mut dyn RngCore) -> Option<usize> {
+        (**self).matches(input, at, rng)
+    }
+
+    fn produce(&self, input: &[T], matched: Range<usize>, rng: &mut dyn RngCore, out: &mut Vec<T>) {
+        (**self).produce(input, matched, rng, out)
+    }
+
+    fn span(&self) -> Option<usize> {
+        (**self).span()
+    }
+
+    fn lookbehind(&self) -> usize {
+        (**self).lookbehind()
+    }
+}
+
+/// A type-erased rule, as stored in an [`LSystem`](crate::LSystem).
+pub type BoxedRule<T> = Box<dyn Rule<T> + Send + Sync>;
+
+/// Where rules draw their randomness from while a generation is rewritten.
+pub(crate) enum Randomness<'a> {
+    /// A single generator, drawn from in the order the generation is rewritten.
+    Shared(&'a mut dyn RngCore),
+    /// A fresh [`CounterRng`] for every position of every generation, so the randomness at a
+    /// position does not depend on the order in which positions are rewritten.
+    Positional { seed: u64, generation: u64 },
+}
+
+/// Separates the random numbers used for matching at a position from those used for producing
+/// the replacement, so both can happen at different times.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Phase {
+    Match,
+    Produce,
+}
+
+impl Randomness<'_> {
+    /// Calls `f` with the generator to use for `phase` at `position`.
+    pub(crate) fn at<U>(
+        &mut self,
+        position: usize,
+        phase: Phase,
+        f: impl FnOnce(&mut dyn RngCore) -> U,
+    ) -> U {
+        match self {
+            Randomness::Shared(rng) => f(*rng),
+            Randomness::Positional { seed, generation } => f(&mut CounterRng::keyed(&[
+                *seed,
+                *generation,
+                position as u64,
+                phase as u64,
+            ])),
+        }
+    }
+}
+
+/// Rewrites `input` once, appending the next generation to `out`.
+/// Any slice of the input will have at most one rule applied to it, and the first one matching wins.
+/// Elements that no rule matches are copied unchanged.
+pub(crate) fn rewrite<T: Clone, R: Rule<T>>(
+    input: &[T],
+    rules: &[R],
+    randomness: &mut Randomness,
+    out: &mut Vec<T>,
+) {
+    let Ok(()) = rewrite_checked(input, rules, randomness, out, |_| Ok::<(), Infallible>(()));
+}
+
+/// Like [`rewrite`], but calls `check` with the output before every step and stops as soon as it
+/// returns an error. `check` may also reserve room in the output.
+pub(crate) fn rewrite_checked<T: Clone, R: Rule<T>, E>(
+    input: &[T],
+    rules: &[R],
+    randomness: &mut Randomness,
+    out: &mut Vec<T>,
+    mut check: impl FnMut(&mut Vec<T>) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut i = 0;
+    while i < input.len() {
+        check(out)?;
+        let found = randomness.at(i, Phase::Match, |rng| first_match(input, i, rules, rng));
+        match found {
+            Some((rule, len)) => {
+                randomness.at(i, Phase::Produce, |rng| {
+                    rules[rule].produce(input, i..i + len, rng, out)
+                });
+                i += len;
+            }
+            None => {
+                out.push(input[i].clone());
+                i += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds the index of the first rule matching at `input[at..]` and the number of elements it
+/// consumes.
+pub(crate) fn first_match<T, R: Rule<T>>(
+    input: &[T],
+    at: usize,
+    rules: &[R],
+    rng: &mut dyn RngCore,
+) -> Option<(usize, usize)> {
+    rules
+        .iter()
+        .enumerate()
+        .find_map(|(index, rule)| match rule.matches(input, at, rng) {
+            Some(len @ 1..) => Some((index, len)),
+            _ => None,
+        })
+}
+
+/// Rewrites `axiom` the given number of times, using `rewrite` to compute each generation from
+/// the previous one.
+/// Generations are written alternately into two buffers, so once they have grown to the size of
+/// the last generations no more allocations are needed.
+pub(crate) fn iterate<T>(
+    axiom: Vec<T>,
+    iterations: u32,
+    mut rewrite: impl FnMut(&[T], u32, &mut Vec<T>),
+) -> Vec<T> {
+    let mut current = axiom;
+    let mut next = Vec::with_capacity(current.len() * 2);
+    for generation in 0..iterations {
+        next.clear();
+        rewrite(&current, generation, &mut next);
+        mem::swap(&mut current, &mut next);
+    }
+    current
+}
+
+/// Rewrites `axiom` the given number of times, drawing randomness from `rng` in order.
+pub(crate) fn expand<T: Clone, R: Rule<T>>(
+    axiom: Vec<T>,
+    rules: &[R],
+    iterations: u32,
+    rng: &mut dyn RngCore,
+) -> Vec<T> {
+    let mut randomness = Randomness::Shared(rng);
+    iterate(axiom, iterations, |input, _, out| {
+        rewrite(input, rules, &mut randomness, out)
+    })
+}
+
+/// Rewrites `axiom` the given number of times, with [`Randomness::Positional`] randomness.
+pub(crate) fn expand_positional<T: Clone, R: Rule<T>>(
+    axiom: Vec<T>,
+    rules: &[R],
+    iterations: u32,
+    seed: u64,
+) -> Vec<T> {
+    iterate(axiom, iterations, |input, generation, out| {
+        let generation = u64::from(generation);
+        rewrite(
+            input,
+            rules,
+            &mut Randomness::Positional { seed, generation },
+            out,
+        )
+    })
+}
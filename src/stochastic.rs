@@ -0,0 +1,344 @@
+//! Non-deterministic L-Systems, where rules only apply with a certain chance.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_core::impls;
+
+use crate::limits::{self, ExpansionError, Limits};
+use crate::rule::{self, Rule};
+
+/// A random number generator seeded with `seed`.
+///
+/// The generator is a ChaCha stream cipher with 8 rounds, whose output is fully specified and
+/// independent of the platform, so the same seed always yields the same L-System.
+pub fn seeded_rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// A counter-based random number generator: the `n`-th number it produces is a pure function of
+/// its key and `n`, so any number of independent generators can be created on the spot from
+/// coordinates like a seed, a generation and a position.
+///
+/// The output is that of SplitMix64, with a key derived by mixing all coordinates. It is fast and
+/// statistically sound for simulations, but not cryptographically secure.
+///
+/// ```
+/// use lsystem::stochastic::CounterRng;
+/// use rand::Rng;
+///
+/// let x: f64 = CounterRng::new(42, 3, 1000).gen();
+/// assert_eq!(x, CounterRng::new(42, 3, 1000).gen::<f64>());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterRng {
+    key: u64,
+    counter: u64,
+}
+
+impl CounterRng {
+    /// A generator keyed by three coordinates, typically a seed, a generation and a position.
+    ///
+    /// This is a building block for randomness of your own: the generators that
+    /// [`LSystem::expand_positional`](crate::LSystem::expand_positional) uses are keyed
+    /// differently, so this does not reproduce the numbers drawn by the rules there.
+    pub fn new(seed: u64, generation: u64, position: u64) -> Self {
+        Self::keyed(&[seed, generation, position])
+    }
+
+    /// A generator whose key is derived from any number of coordinates.
+    pub(crate) fn keyed(coordinates: &[u64]) -> Self {
+        let key = coordinates.iter().fold(0, |key, &coordinate| {
+            splitmix64(key ^ splitmix64(coordinate))
+        });
+        CounterRng { key, counter: 0 }
+    }
+}
+
+impl RngCore for CounterRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.counter += 1;
+        splitmix64(
+            self.key
+                .wrapping_add(self.counter.wrapping_mul(GOLDEN_GAMMA)),
+        )
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The increment of SplitMix64, derived from the golden ratio.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The output function of SplitMix64, a bijective mixer of 64 bit values.
+fn splitmix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A rule replacing a slice of elements by a sequence of elements with a chance between 0.0 and 1.0.
+/// The chance is rolled every time the pattern matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChanceRule<T> {
+    pub original: Vec<T>,
+    pub replacement: Vec<T>,
+    pub chance: f32,
+}
+
+impl<T> ChanceRule<T> {
+    pub fn new(original: Vec<T>, replacement: Vec<T>, chance: f32) -> Self {
+        ChanceRule {
+            original,
+            replacement,
+            chance,
+        }
+    }
+}
+
+impl<T> From<(Vec<T>, Vec<T>, f32)> for ChanceRule<T> {
+    fn from((original, replacement, chance): (Vec<T>, Vec<T>, f32)) -> Self {
+        ChanceRule::new(original, replacement, chance)
+    }
+}
+
+impl<T: PartialEq + Clone> Rule<T> for ChanceRule<T> {
+    fn matches(&self, input: &[T], at: usize, rng: &mut dyn RngCore) -> Option<usize> {
+        (input[at..].starts_with(&self.original) && rng.gen_range(0.0..=1.0) <= self.chance)
+            .then_some(self.original.len())
+    }
+
+    fn produce(&self, _: &[T], _: Range<usize>, _: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.extend_from_slice(&self.replacement);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(self.original.len())
+    }
+}
+
+/// A rule replacing a slice of elements by one of several alternatives, chosen at random
+/// according to their weights every time the pattern matches.
+///
+/// Weights are relative and normalised automatically, so `[(b, 1.0), (c, 1.0)]` and
+/// `[(b, 0.5), (c, 0.5)]` both choose between `b` and `c` with a 50% chance each.
+///
+/// ```
+/// use lsystem::stochastic::WeightedRule;
+/// use lsystem::LSystem;
+///
+/// let rule = WeightedRule::new(vec!['A'], vec![(vec!['B'], 1.0), (vec!['C'], 3.0)]).unwrap();
+/// let result = LSystem::new(vec!['A'; 8]).with_rule(rule).expand_seeded(1, 0);
+/// assert!(result.iter().all(|c| *c == 'B' || *c == 'C'));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedRule<T> {
+    original: Vec<T>,
+    replacements: Vec<Vec<T>>,
+    /// The cumulative, normalised weights of the replacements, the last one is exactly 1.0.
+    thresholds: Vec<f64>,
+}
+
+impl<T> WeightedRule<T> {
+    /// Fails if there are no replacements, if any weight is negative or not finite, or if all
+    /// weights are zero.
+    pub fn new(original: Vec<T>, replacements: Vec<(Vec<T>, f64)>) -> Result<Self, WeightError> {
+        let weights: Vec<f64> = replacements.iter().map(|(_, w)| *w).collect();
+        let thresholds = thresholds(&weights)?;
+        let replacements = replacements.into_iter().map(|(r, _)| r).collect();
+        Ok(WeightedRule {
+            original,
+            replacements,
+            thresholds,
+        })
+    }
+
+    /// The pattern this rule replaces.
+    pub fn original(&self) -> &[T] {
+        &self.original
+    }
+
+    /// The alternatives with their normalised probabilities, which add up to 1.0.
+    pub fn alternatives(&self) -> impl Iterator<Item = (&[T], f64)> {
+        let previous = std::iter::once(0.0).chain(self.thresholds.iter().copied());
+        self.replacements
+            .iter()
+            .zip(self.thresholds.iter().zip(previous))
+            .map(|(replacement, (t, p))| (replacement.as_slice(), t - p))
+    }
+}
+
+impl<T: PartialEq + Clone> Rule<T> for WeightedRule<T> {
+    fn matches(&self, input: &[T], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        input[at..]
+            .starts_with(&self.original)
+            .then_some(self.original.len())
+    }
+
+    fn produce(&self, _: &[T], _: Range<usize>, rng: &mut dyn RngCore, out: &mut Vec<T>) {
+        out.extend_from_slice(&self.replacements[choose(&self.thresholds, rng)]);
+    }
+
+    fn span(&self) -> Option<usize> {
+        Some(self.original.len())
+    }
+}
+
+/// The cumulative, normalised `weights`, the last one is exactly 1.0.
+pub(crate) fn thresholds(weights: &[f64]) -> Result<Vec<f64>, WeightError> {
+    if weights.is_empty() {
+        return Err(WeightError::Empty);
+    }
+    if let Some(index) = weights.iter().position(|w| !w.is_finite()) {
+        return Err(WeightError::NotFinite { index });
+    }
+    if let Some(index) = weights.iter().position(|w| *w < 0.0) {
+        return Err(WeightError::Negative { index });
+    }
+    let total: f64 = weights.iter().sum();
+    if total == 0.0 {
+        return Err(WeightError::AllZero);
+    }
+    if !total.is_finite() {
+        return Err(WeightError::TotalNotFinite);
+    }
+    // summed in the same order as the total, so the last threshold is exactly 1.0
+    let mut cumulative = 0.0;
+    Ok(weights
+        .iter()
+        .map(|weight| {
+            cumulative += weight;
+            cumulative / total
+        })
+        .collect())
+}
+
+/// Picks an index at random, with the probabilities given by cumulative `thresholds`.
+pub(crate) fn choose(thresholds: &[f64], rng: &mut dyn RngCore) -> usize {
+    let roll: f64 = rng.gen();
+    thresholds.partition_point(|&t| t <= roll)
+}
+
+/// The reasons the weights of a [`WeightedRule`] can be invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightError {
+    /// There are no alternatives to choose from.
+    Empty,
+    /// The weight of the alternative at `index` is negative.
+    Negative { index: usize },
+    /// The weight of the alternative at `index` is infinite or NaN.
+    NotFinite { index: usize },
+    /// No alternative has a positive weight.
+    AllZero,
+    /// The weights are finite, but too large to be added up.
+    TotalNotFinite,
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightError::Empty => write!(f, "no alternatives to choose from"),
+            WeightError::Negative { index } => {
+                write!(f, "the weight of alternative {index} is negative")
+            }
+            WeightError::NotFinite { index } => {
+                write!(f, "the weight of alternative {index} is not finite")
+            }
+            WeightError::AllZero => write!(f, "all weights are zero"),
+            WeightError::TotalNotFinite => write!(f, "the sum of the weights is not finite"),
+        }
+    }
+}
+
+impl Error for WeightError {}
+
+/// an implementation of a non-deterministic L-system
+/// Each rule is a tuple of (original, replacement, chance)
+/// where original is a `Vec<T>` that will be replaced by replacement with a chance between 0.0 and 1.0
+/// Note that each chance is calculated individually - so to express that A will be replaced either by B or C with a
+/// 50% chance each, the rules are `vec![(vec![A], vec![B], 0.5), (vec![A], vec![C], 1.0)]`
+/// A [`WeightedRule`] expresses such choices directly.
+///
+/// ```
+/// use lsystem::stochastic::random_lsystem;
+///
+/// let rules = vec![(vec!['A'], vec!['B'], 0.5), (vec!['A'], vec!['C'], 1.0)];
+/// let result = random_lsystem(vec!['A'], &rules, 1);
+/// assert!(result == vec!['B'] || result == vec!['C']);
+/// ```
+pub fn random_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>, f32)],
+    iterations: u32,
+) -> Vec<T> {
+    random_lsystem_with_rng(axiom, rules, iterations, rand::thread_rng())
+}
+
+/// Like [`random_lsystem`], but all chances are rolled with `rng`, which makes the result
+/// reproducible when `rng` is seeded, e.g. by [`seeded_rng`].
+///
+/// ```
+/// use lsystem::stochastic::{random_lsystem_with_rng, seeded_rng};
+///
+/// let rules = vec![(vec!['A'], vec!['A', 'B'], 0.5), (vec!['B'], vec!['A'], 0.75)];
+/// let first = random_lsystem_with_rng(vec!['A'], &rules, 8, seeded_rng(7));
+/// let second = random_lsystem_with_rng(vec!['A'], &rules, 8, seeded_rng(7));
+/// assert_eq!(first, second);
+/// ```
+pub fn random_lsystem_with_rng<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>, f32)],
+    iterations: u32,
+    mut rng: impl RngCore,
+) -> Vec<T> {
+    let rules: Vec<ChanceRule<T>> = rules.iter().cloned().map(ChanceRule::from).collect();
+    rule::expand(axiom, &rules, iterations, &mut rng)
+}
+
+/// Like [`random_lsystem`], but stops as soon as a generation exceeds `limits`, returning the
+/// last generation that stayed within them.
+pub fn try_random_lsystem<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>, f32)],
+    iterations: u32,
+    limits: &Limits,
+) -> Result<Vec<T>, ExpansionError<T>> {
+    try_random_lsystem_with_rng(axiom, rules, iterations, rand::thread_rng(), limits)
+}
+
+/// Like [`try_random_lsystem`], but all chances are rolled with `rng`.
+///
+/// ```
+/// use lsystem::limits::{Exceeded, Limits};
+/// use lsystem::stochastic::{seeded_rng, try_random_lsystem_with_rng};
+///
+/// let rules = vec![(vec!['A'], vec!['A', 'A'], 1.0)];
+/// let limits = Limits::new().max_symbols(1000);
+/// let error = try_random_lsystem_with_rng(vec!['A'], &rules, 64, seeded_rng(7), &limits)
+///     .unwrap_err();
+/// assert_eq!((error.exceeded, error.generation), (Exceeded::Symbols, 9));
+/// ```
+pub fn try_random_lsystem_with_rng<T: PartialEq + Clone>(
+    axiom: Vec<T>,
+    rules: &[(Vec<T>, Vec<T>, f32)],
+    iterations: u32,
+    mut rng: impl RngCore,
+    limits: &Limits,
+) -> Result<Vec<T>, ExpansionError<T>> {
+    let rules: Vec<ChanceRule<T>> = rules.iter().cloned().map(ChanceRule::from).collect();
+    limits::try_expand(axiom, &rules, iterations, &mut rng, limits)
+}
@@ -0,0 +1,140 @@
+//! Lazy, depth-first expansion that never holds a whole generation in memory.
+
+use std::collections::VecDeque;
+
+use rand::RngCore;
+
+use crate::rule::{self, BoxedRule};
+
+/// An iterator over the elements of a single generation of an [`LSystem`](crate::LSystem),
+/// computed on demand.
+///
+/// Instead of materialising every generation, each generation is a stage that pulls elements
+/// from the stage before it, keeping only as many elements as the rules span and look behind, and
+/// the replacement it is currently emitting. Memory use is therefore proportional to the number of
+/// iterations times the longest rule, regardless of how long the generation is. Pulling an
+/// element recurses through the stages, so the stack depth grows with the number of iterations.
+///
+/// Randomness is drawn in depth-first order, so stochastic rules produce a generation that is
+/// different from (but distributed like) the one [`LSystem::expand`](crate::LSystem::expand)
+/// produces from the same random numbers.
+pub struct Stream<'a, T, R> {
+    rules: &'a [BoxedRule<T>],
+    span: usize,
+    lookbehind: usize,
+    axiom: std::slice::Iter<'a, T>,
+    stages: Vec<Stage<T>>,
+    scratch: Vec<T>,
+    rng: R,
+}
+
+/// The elements of one generation that have been pulled from the previous one but not yet
+/// rewritten, and the ones that have been produced but not yet passed on.
+struct Stage<T> {
+    /// Starts with `history` elements that have already been rewritten, but might still be
+    /// looked at by rules matching after them.
+    window: VecDeque<T>,
+    history: usize,
+    pending: VecDeque<T>,
+    exhausted: bool,
+}
+
+impl<'a, T: Clone, R: RngCore> Stream<'a, T, R> {
+    /// Returns `None` if any of the rules does not report a [`span`](crate::Rule::span).
+    pub(crate) fn new(
+        axiom: &'a [T],
+        rules: &'a [BoxedRule<T>],
+        iterations: u32,
+        rng: R,
+    ) -> Option<Self> {
+        let span = rules
+            .iter()
+            .map(|rule| rule.span())
+            .try_fold(1, |max, span| Some(max.max(span?)))?;
+        let lookbehind = rules
+            .iter()
+            .map(|rule| rule.lookbehind())
+            .max()
+            .unwrap_or(0);
+        let stages = (0..iterations)
+            .map(|_| Stage {
+                window: VecDeque::with_capacity(lookbehind + span),
+                history: 0,
+                pending: VecDeque::new(),
+                exhausted: false,
+            })
+            .collect();
+        Some(Stream {
+            rules,
+            span,
+            lookbehind,
+            axiom: axiom.iter(),
+            stages,
+            scratch: Vec::new(),
+            rng,
+        })
+    }
+
+    /// Pulls the next element of the given generation.
+    fn pull(&mut self, generation: usize) -> Option<T> {
+        if generation == 0 {
+            return self.axiom.next().cloned();
+        }
+        let stage = generation - 1;
+        loop {
+            if let Some(elem) = self.stages[stage].pending.pop_front() {
+                return Some(elem);
+            }
+            while !self.stages[stage].exhausted
+                && self.stages[stage].window.len() < self.stages[stage].history + self.span
+            {
+                match self.pull(stage) {
+                    Some(elem) => self.stages[stage].window.push_back(elem),
+                    None => self.stages[stage].exhausted = true,
+                }
+            }
+
+            let Stream {
+                rules,
+                lookbehind,
+                stages,
+                scratch,
+                rng,
+                ..
+            } = self;
+            let Stage {
+                window,
+                history,
+                pending,
+                ..
+            } = &mut stages[stage];
+            let at = *history;
+            if window.len() == at {
+                return None;
+            }
+            let input = window.make_contiguous();
+            match rule::first_match(input, at, rules, rng) {
+                Some((rule, len)) => {
+                    rules[rule].produce(input, at..at + len, rng, scratch);
+                    pending.extend(scratch.drain(..));
+                    *history += len;
+                }
+                None => {
+                    pending.push_back(input[at].clone());
+                    *history += 1;
+                }
+            }
+            let forget = history.saturating_sub(*lookbehind);
+            window.drain(..forget);
+            *history -= forget;
+        }
+    }
+}
+
+impl<T: Clone, R: RngCore> Iterator for Stream<'_, T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.pull(self.stages.len())
+    }
+}
@@ -0,0 +1,223 @@
+//! Export of turtle drawings as SVG images.
+
+use std::fmt::Write;
+
+use crate::format::number;
+use crate::turtle::{bounds, Point, Segment};
+
+/// The colour and width of a line, in SVG syntax and drawing units respectively. The colour is
+/// escaped when written, so it cannot break the markup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub color: String,
+    pub width: f64,
+}
+
+impl Stroke {
+    pub fn new(color: impl Into<String>, width: f64) -> Self {
+        Stroke {
+            color: color.into(),
+            width,
+        }
+    }
+}
+
+/// Renders segments as an SVG image whose `viewBox` fits the drawing.
+///
+/// Connected segments with the same stroke are merged into a single `<polyline>`, which keeps
+/// the images of large generations small. The y axis of the drawing points up, as in
+/// [`Turtle`](crate::turtle::Turtle), and is flipped to point down as in SVG.
+///
+/// ```
+/// use lsystem::svg::Svg;
+/// use lsystem::turtle::Turtle;
+///
+/// let square: Vec<char> = "F+F+F+F".chars().collect();
+/// let image = Svg::new().margin(0.5).render(&Turtle::new(10.0, 90.0).interpret(&square));
+/// assert!(image.contains(r#"viewBox="-11 -11 12 12""#));
+/// assert_eq!(image.matches("<polyline").count(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Svg {
+    stroke: Stroke,
+    margin: f64,
+    width: Option<f64>,
+    background: Option<String>,
+    precision: usize,
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Svg {
+            stroke: Stroke::new("black", 1.0),
+            margin: 1.0,
+            width: None,
+            background: None,
+            precision: 3,
+        }
+    }
+}
+
+impl Svg {
+    /// Black lines one unit wide, with a margin of one unit and no fixed size.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stroke of segments without one of their own.
+    pub fn stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = stroke;
+        self
+    }
+
+    /// The space around the drawing, in drawing units.
+    pub fn margin(mut self, margin: f64) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// The width of the image in pixels, the height follows from the aspect ratio of the drawing.
+    /// Without it the image scales to whatever it is embedded in.
+    pub fn width(mut self, width: f64) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Fills the background with `color`, instead of leaving it transparent.
+    pub fn background(mut self, color: impl Into<String>) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+
+    /// The number of decimal places of the coordinates.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Renders `segments`, all with the same stroke.
+    pub fn render(&self, segments: &[Segment]) -> String {
+        self.render_styled(segments, |_, _| None)
+    }
+
+    /// Renders `segments`, where `style` can give each segment, identified by its index, a stroke
+    /// of its own instead of the default one.
+    ///
+    /// ```
+    /// use lsystem::svg::{Stroke, Svg};
+    /// use lsystem::turtle::Turtle;
+    ///
+    /// let segments = Turtle::new(1.0, 90.0).interpret(&['F', 'F', 'F']);
+    /// let image = Svg::new().render_styled(&segments, |i, _| {
+    ///     (i == 2).then(|| Stroke::new("red", 0.5))
+    /// });
+    /// assert_eq!(image.matches("<polyline").count(), 2);
+    /// assert!(image.contains(r#"stroke="red" stroke-width="0.5""#));
+    /// ```
+    pub fn render_styled(
+        &self,
+        segments: &[Segment],
+        style: impl Fn(usize, &Segment) -> Option<Stroke>,
+    ) -> String {
+        let strokes: Vec<Option<Stroke>> = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| style(i, segment))
+            .collect();
+        let stroke = |i: usize| strokes[i].as_ref().unwrap_or(&self.stroke);
+
+        let (min, max) = bounds(segments).unwrap_or_default();
+        let widest = (0..segments.len())
+            .map(|i| stroke(i).width)
+            .fold(0.0, f64::max);
+        let pad = self.margin + widest / 2.0;
+        let (x, y) = (min.x - pad, -max.y - pad);
+        let (w, h) = (max.x - min.x + 2.0 * pad, max.y - min.y + 2.0 * pad);
+
+        let mut svg = String::new();
+        write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}""#,
+            self.number(x),
+            self.number(y),
+            self.number(w),
+            self.number(h)
+        )
+        .unwrap();
+        if let Some(width) = self.width {
+            let height = if w > 0.0 { width * h / w } else { width };
+            write!(
+                svg,
+                r#" width="{}" height="{}""#,
+                self.number(width),
+                self.number(height)
+            )
+            .unwrap();
+        }
+        svg.push_str(">\n");
+        if let Some(background) = &self.background {
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                self.number(x),
+                self.number(y),
+                self.number(w),
+                self.number(h),
+                escape(background)
+            )
+            .unwrap();
+        }
+        svg.push_str("<g fill=\"none\" stroke-linecap=\"round\" stroke-linejoin=\"round\">\n");
+
+        let mut i = 0;
+        while i < segments.len() {
+            // extend the polyline for as long as the segments connect and share a stroke
+            let mut points = vec![self.point(segments[i].start), self.point(segments[i].end)];
+            let mut next = i + 1;
+            while next < segments.len()
+                && self.point(segments[next].start) == *points.last().unwrap()
+                && stroke(next) == stroke(i)
+            {
+                points.push(self.point(segments[next].end));
+                next += 1;
+            }
+            let Stroke { color, width } = stroke(i);
+            writeln!(
+                svg,
+                r#"<polyline points="{}" stroke="{}" stroke-width="{}"/>"#,
+                points.join(" "),
+                escape(color),
+                self.number(*width)
+            )
+            .unwrap();
+            i = next;
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    fn number(&self, value: f64) -> String {
+        number(value, self.precision)
+    }
+
+    /// Formats a point in SVG coordinates, where the y axis points down.
+    fn point(&self, point: Point) -> String {
+        format!("{},{}", self.number(point.x), self.number(-point.y))
+    }
+}
+
+/// Escapes the characters with a meaning in XML, for use in attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
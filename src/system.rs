@@ -0,0 +1,234 @@
+//! A single L-System type that mixes any kind of [`Rule`].
+
+use rand::rngs::ThreadRng;
+use rand::RngCore;
+
+use crate::generations::Generations;
+use crate::limits::{self, ExpansionError, Limits};
+use crate::rule::{self, BoxedRule, Rule};
+use crate::stochastic::seeded_rng;
+use crate::stream::Stream;
+
+/// An L-System made up of an axiom and an ordered set of rules.
+///
+/// Rules of different flavours can be mixed freely; at every position of a generation they are
+/// tried in the order they were added, and the first one matching wins.
+///
+/// Every method that needs randomness for stochastic rules comes in three variants: one using
+/// [`rand::thread_rng`], one taking any random number generator (`_with_rng`), and for
+/// [`expand`](LSystem::expand) one taking a seed that reproduces the same result on every run
+/// and platform (`_seeded`).
+///
+/// ```
+/// use lsystem::deterministic::SymbolRule;
+/// use lsystem::functional::FnRule;
+/// use lsystem::LSystem;
+///
+/// let system = LSystem::new(vec!['A'])
+///     .with_rule(SymbolRule::new('A', vec!['A', 'B']))
+///     .with_rule(FnRule::new(vec!['B'], |_: Vec<char>| vec!['A']));
+/// assert_eq!(system.expand(3), vec!['A', 'B', 'A', 'A', 'B']);
+/// ```
+pub struct LSystem<T> {
+    axiom: Vec<T>,
+    rules: Vec<BoxedRule<T>>,
+}
+
+impl<T: Clone> LSystem<T> {
+    /// Creates an L-System without any rules, i.e. every element is a constant.
+    pub fn new(axiom: Vec<T>) -> Self {
+        LSystem {
+            axiom,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Appends a rule, which has lower precedence than all rules added before it.
+    pub fn with_rule(mut self, rule: impl Rule<T> + Send + Sync + 'static) -> Self {
+        self.push_rule(rule);
+        self
+    }
+
+    /// Appends a rule, which has lower precedence than all rules added before it.
+    pub fn push_rule(&mut self, rule: impl Rule<T> + Send + Sync + 'static) {
+        self.rules.push(Box::new(rule));
+    }
+
+    /// The starting state of the system.
+    pub fn axiom(&self) -> &[T] {
+        &self.axiom
+    }
+
+    /// The rules of the system, in order of precedence.
+    pub fn rules(&self) -> &[BoxedRule<T>] {
+        &self.rules
+    }
+
+    /// Computes the generation after the given number of iterations.
+    pub fn expand(&self, iterations: u32) -> Vec<T> {
+        rule::expand(
+            self.axiom.clone(),
+            &self.rules,
+            iterations,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Like [`LSystem::expand`], drawing randomness from `rng`.
+    pub fn expand_with_rng(&self, iterations: u32, mut rng: impl RngCore) -> Vec<T> {
+        rule::expand(self.axiom.clone(), &self.rules, iterations, &mut rng)
+    }
+
+    /// Like [`LSystem::expand`], drawing randomness from a generator seeded with `seed`.
+    /// See [`seeded_rng`] for the reproducibility guarantees.
+    ///
+    /// ```
+    /// use lsystem::stochastic::ChanceRule;
+    /// use lsystem::LSystem;
+    ///
+    /// let system = LSystem::new(vec!['A']).with_rule(ChanceRule::new(vec!['A'], vec!['A', 'A'], 0.5));
+    /// assert_eq!(system.expand_seeded(10, 42), system.expand_seeded(10, 42));
+    /// ```
+    pub fn expand_seeded(&self, iterations: u32, seed: u64) -> Vec<T> {
+        self.expand_with_rng(iterations, seeded_rng(seed))
+    }
+
+    /// Like [`LSystem::expand`], but rewrites large generations on several threads.
+    ///
+    /// The output is identical to that of [`LSystem::expand`] for deterministic rules. Stochastic
+    /// rules draw their randomness in a different order, so their output differs from the
+    /// sequential one.
+    ///
+    /// ```
+    /// use lsystem::context::SliceRule;
+    /// use lsystem::LSystem;
+    ///
+    /// let system = LSystem::new(vec!['A'])
+    ///     .with_rule(SliceRule::new(vec!['B', 'A'], vec!['A']))
+    ///     .with_rule(SliceRule::new(vec!['A'], vec!['A', 'B', 'A']));
+    /// assert_eq!(system.par_expand(12), system.expand(12));
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn par_expand(&self, iterations: u32) -> Vec<T>
+    where
+        T: Send + Sync,
+    {
+        self.par_expand_with_rng(iterations, rand::thread_rng())
+    }
+
+    /// Like [`LSystem::par_expand`], drawing randomness from `rng`.
+    #[cfg(feature = "parallel")]
+    pub fn par_expand_with_rng(&self, iterations: u32, mut rng: impl RngCore) -> Vec<T>
+    where
+        T: Send + Sync,
+    {
+        crate::parallel::expand(self.axiom.clone(), &self.rules, iterations, &mut rng)
+    }
+
+    /// Like [`LSystem::expand_positional`], but rewrites large generations on several threads.
+    /// The output is identical to that of [`LSystem::expand_positional`], for stochastic rules too.
+    #[cfg(feature = "parallel")]
+    pub fn par_expand_positional(&self, iterations: u32, seed: u64) -> Vec<T>
+    where
+        T: Send + Sync,
+    {
+        crate::parallel::expand_positional(self.axiom.clone(), &self.rules, iterations, seed)
+    }
+
+    /// Like [`LSystem::expand`], but every position of every generation draws its randomness from
+    /// its own [`CounterRng`](crate::stochastic::CounterRng), derived from `seed`, the number of
+    /// the generation and the position in it.
+    ///
+    /// Since the randomness does not depend on the order in which positions are rewritten, the
+    /// result is the same as that of `par_expand_positional` (with the `parallel` feature) with the
+    /// same seed.
+    ///
+    /// ```
+    /// use lsystem::stochastic::ChanceRule;
+    /// use lsystem::LSystem;
+    ///
+    /// let system = LSystem::new(vec!['A']).with_rule(ChanceRule::new(vec!['A'], vec!['A', 'A'], 0.5));
+    /// assert_eq!(system.expand_positional(10, 42), system.expand_positional(10, 42));
+    /// ```
+    pub fn expand_positional(&self, iterations: u32, seed: u64) -> Vec<T> {
+        rule::expand_positional(self.axiom.clone(), &self.rules, iterations, seed)
+    }
+
+    /// Computes the generation after the given number of iterations, unless that exceeds `limits`.
+    /// The limits are checked while each generation is being rewritten, so a generation that is
+    /// too large is abandoned before it is complete.
+    pub fn try_expand(
+        &self,
+        iterations: u32,
+        limits: &Limits,
+    ) -> Result<Vec<T>, ExpansionError<T>> {
+        limits::try_expand(
+            self.axiom.clone(),
+            &self.rules,
+            iterations,
+            &mut rand::thread_rng(),
+            limits,
+        )
+    }
+
+    /// Like [`LSystem::try_expand`], drawing randomness from `rng`.
+    pub fn try_expand_with_rng(
+        &self,
+        iterations: u32,
+        limits: &Limits,
+        mut rng: impl RngCore,
+    ) -> Result<Vec<T>, ExpansionError<T>> {
+        limits::try_expand(
+            self.axiom.clone(),
+            &self.rules,
+            iterations,
+            &mut rng,
+            limits,
+        )
+    }
+
+    /// Iterates over all generations, starting with the axiom.
+    ///
+    /// ```
+    /// use lsystem::deterministic::SymbolRule;
+    /// use lsystem::LSystem;
+    ///
+    /// let system = LSystem::new(vec!['A'])
+    ///     .with_rule(SymbolRule::new('A', vec!['A', 'B']))
+    ///     .with_rule(SymbolRule::new('B', vec!['A']));
+    /// let lengths: Vec<usize> = system.generations().take(6).map(|g| g.len()).collect();
+    /// assert_eq!(lengths, vec![1, 2, 3, 5, 8, 13]);
+    /// ```
+    pub fn generations(&self) -> Generations<'_, T, ThreadRng> {
+        self.generations_with_rng(rand::thread_rng())
+    }
+
+    /// Like [`LSystem::generations`], drawing randomness from `rng`.
+    pub fn generations_with_rng<R: RngCore>(&self, rng: R) -> Generations<'_, T, R> {
+        Generations::new(&self.axiom, &self.rules, rng)
+    }
+
+    /// Lazily computes the generation after the given number of iterations, element by element.
+    /// See [`Stream`] for the memory guarantees.
+    ///
+    /// Returns `None` if any of the rules does not report a [`span`](Rule::span), since those
+    /// might need to see the whole generation at once.
+    ///
+    /// ```
+    /// use lsystem::deterministic::SymbolRule;
+    /// use lsystem::LSystem;
+    ///
+    /// let koch = LSystem::new(vec!['F'])
+    ///     .with_rule(SymbolRule::new('F', "F+F-F-F+F".chars().collect()));
+    /// let forward_steps = koch.stream(7).unwrap().filter(|c| *c == 'F').count();
+    /// assert_eq!(forward_steps, 5usize.pow(7));
+    /// ```
+    pub fn stream(&self, iterations: u32) -> Option<Stream<'_, T, ThreadRng>> {
+        self.stream_with_rng(iterations, rand::thread_rng())
+    }
+
+    /// Like [`LSystem::stream`], drawing randomness from `rng`.
+    pub fn stream_with_rng<R: RngCore>(&self, iterations: u32, rng: R) -> Option<Stream<'_, T, R>> {
+        Stream::new(&self.axiom, &self.rules, iterations, rng)
+    }
+}
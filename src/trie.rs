@@ -0,0 +1,95 @@
+//! The trie of rule patterns shared by [`PatternTrie`](crate::context::PatternTrie) and
+//! [`Automaton`](crate::automaton::Automaton).
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The patterns of a list of slice rules, stored in a trie with their replacements.
+#[derive(Debug, Clone)]
+pub(crate) struct Trie<T> {
+    pub(crate) nodes: Vec<Node<T>>,
+    /// The length of each rule's pattern.
+    pub(crate) patterns: Vec<usize>,
+    pub(crate) replacements: Vec<Vec<T>>,
+    /// The length of the longest pattern.
+    pub(crate) depth: usize,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Node<T> {
+    pub(crate) children: HashMap<T, usize>,
+    /// The length of the path from the root, i.e. of the patterns ending here.
+    pub(crate) depth: usize,
+    /// The first rule whose pattern is exactly this node's path.
+    pub(crate) rule: Option<usize>,
+}
+
+impl<T> Node<T> {
+    fn new(depth: usize) -> Self {
+        Node {
+            children: HashMap::new(),
+            depth,
+            rule: None,
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Trie<T> {
+    /// Rules with an empty pattern never match, as in [`Rule::matches`](crate::Rule::matches).
+    pub(crate) fn new(rules: impl IntoIterator<Item = (Vec<T>, Vec<T>)>) -> Self {
+        let mut trie = Trie {
+            nodes: vec![Node::new(0)],
+            patterns: Vec::new(),
+            replacements: Vec::new(),
+            depth: 0,
+        };
+        for (original, replacement) in rules {
+            let mut node = 0;
+            for elem in &original {
+                node = match trie.nodes[node].children.get(elem) {
+                    Some(&child) => child,
+                    None => {
+                        let child = trie.nodes.len();
+                        trie.nodes.push(Node::new(trie.nodes[node].depth + 1));
+                        trie.nodes[node].children.insert(elem.clone(), child);
+                        child
+                    }
+                };
+            }
+            if node != 0 && trie.nodes[node].rule.is_none() {
+                trie.nodes[node].rule = Some(trie.replacements.len());
+            }
+            trie.depth = trie.depth.max(original.len());
+            trie.patterns.push(original.len());
+            trie.replacements.push(replacement);
+        }
+        trie
+    }
+
+    /// The first rule matching at `input[at..]`, and the length of its pattern.
+    pub(crate) fn find(&self, input: &[T], at: usize) -> Option<(usize, usize)> {
+        let mut node = 0;
+        let mut best: Option<(usize, usize)> = None;
+        for (len, elem) in input[at..].iter().enumerate() {
+            match self.nodes[node].children.get(elem) {
+                Some(&child) => node = child,
+                None => break,
+            }
+            if let Some(rule) = self.nodes[node].rule {
+                if best.is_none_or(|(first, _)| rule < first) {
+                    best = Some((rule, len + 1));
+                }
+            }
+        }
+        best
+    }
+
+    /// Appends the replacement of the rule that matched `matched`, which is the first one whose
+    /// pattern is exactly that slice.
+    pub(crate) fn produce(&self, matched: &[T], out: &mut Vec<T>) {
+        let node = matched
+            .iter()
+            .fold(0, |node, elem| self.nodes[node].children[elem]);
+        out.extend_from_slice(&self.replacements[self.nodes[node].rule.unwrap()]);
+    }
+}
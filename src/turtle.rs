@@ -0,0 +1,198 @@
+//! Turtle graphics, which turn a generation into a drawing by reading its elements as commands.
+
+use crate::parametric::Module;
+
+/// A command the turtle understands, with an optional argument overriding the configured step
+/// length or turning angle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Moves forward, drawing a line.
+    Forward(Option<f64>),
+    /// Moves forward without drawing.
+    Move(Option<f64>),
+    /// Turns counterclockwise, by an angle in degrees.
+    Left(Option<f64>),
+    /// Turns clockwise, by an angle in degrees.
+    Right(Option<f64>),
+    /// Turns by 180 degrees.
+    TurnAround,
+    /// Saves the state of the turtle, to start a branch.
+    Push,
+    /// Restores the last saved state, to end a branch.
+    Pop,
+}
+
+/// Elements with a standard meaning as turtle commands, see [`Turtle::interpret`].
+pub trait AsCommand {
+    fn as_command(&self) -> Option<Command>;
+}
+
+/// `F` draws a line, `f` moves, `+` turns left, `-` turns right, `|` turns around, and `[` and `]`
+/// start and end a branch. All other characters are ignored.
+impl AsCommand for char {
+    fn as_command(&self) -> Option<Command> {
+        Some(match self {
+            'F' => Command::Forward(None),
+            'f' => Command::Move(None),
+            '+' => Command::Left(None),
+            '-' => Command::Right(None),
+            '|' => Command::TurnAround,
+            '[' => Command::Push,
+            ']' => Command::Pop,
+            _ => return None,
+        })
+    }
+}
+
+/// Like the symbols on their own, where the first parameter, if any, replaces the step length of
+/// `F` and `f` and the angle of `+` and `-`.
+impl AsCommand for Module<char> {
+    fn as_command(&self) -> Option<Command> {
+        let argument = self.params.first().copied();
+        Some(match self.symbol.as_command()? {
+            Command::Forward(_) => Command::Forward(argument),
+            Command::Move(_) => Command::Move(argument),
+            Command::Left(_) => Command::Left(argument),
+            Command::Right(_) => Command::Right(argument),
+            command => command,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+}
+
+/// A line drawn by the turtle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Point,
+    pub end: Point,
+}
+
+/// The configuration of a turtle: how far it moves and turns, and where it starts.
+///
+/// ```
+/// use lsystem::turtle::{Point, Turtle};
+///
+/// let square: Vec<char> = "F+F+F+F".chars().collect();
+/// let segments = Turtle::new(1.0, 90.0).interpret(&square);
+/// assert_eq!(segments.len(), 4);
+/// assert!((segments[3].end.x - 0.0).abs() < 1e-9 && (segments[3].end.y - 0.0).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turtle {
+    pub step: f64,
+    /// The default turning angle, in degrees.
+    pub angle: f64,
+    pub origin: Point,
+    /// The initial heading, in degrees counterclockwise from the positive x axis.
+    pub heading: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    position: Point,
+    heading: f64,
+}
+
+impl Turtle {
+    /// A turtle starting at the origin, heading up the y axis.
+    pub fn new(step: f64, angle: f64) -> Self {
+        Turtle {
+            step,
+            angle,
+            origin: Point::default(),
+            heading: 90.0,
+        }
+    }
+
+    pub fn with_origin(mut self, origin: Point) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_heading(mut self, heading: f64) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    /// Draws `elements`, using their standard meaning as commands.
+    pub fn interpret<T: AsCommand>(&self, elements: &[T]) -> Vec<Segment> {
+        self.interpret_with(elements, T::as_command)
+    }
+
+    /// Draws `elements`, using `command` to look up the command for each of them. Elements
+    /// without a command are ignored, and so are branches that end without having started.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use lsystem::turtle::{Command, Turtle};
+    ///
+    /// let commands = HashMap::from([('A', Command::Forward(None)), ('L', Command::Left(None))]);
+    /// let segments = Turtle::new(1.0, 90.0).interpret_with(&['A', 'L', 'A'], |e| commands.get(e).copied());
+    /// assert_eq!(segments.len(), 2);
+    /// ```
+    pub fn interpret_with<T>(
+        &self,
+        elements: &[T],
+        command: impl Fn(&T) -> Option<Command>,
+    ) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut state = State {
+            position: self.origin,
+            heading: self.heading,
+        };
+        let mut stack = Vec::new();
+        for element in elements {
+            let Some(command) = command(element) else {
+                continue;
+            };
+            match command {
+                Command::Forward(distance) | Command::Move(distance) => {
+                    let distance = distance.unwrap_or(self.step);
+                    let (sin, cos) = state.heading.to_radians().sin_cos();
+                    let start = state.position;
+                    state.position = Point::new(start.x + distance * cos, start.y + distance * sin);
+                    if let Command::Forward(_) = command {
+                        segments.push(Segment {
+                            start,
+                            end: state.position,
+                        });
+                    }
+                }
+                Command::Left(angle) => state.heading += angle.unwrap_or(self.angle),
+                Command::Right(angle) => state.heading -= angle.unwrap_or(self.angle),
+                Command::TurnAround => state.heading += 180.0,
+                Command::Push => stack.push(state),
+                Command::Pop => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+            }
+        }
+        segments
+    }
+}
+
+/// The lower left and upper right corners of the box around all segments.
+pub(crate) fn bounds(segments: &[Segment]) -> Option<(Point, Point)> {
+    let mut points = segments.iter().flat_map(|s| [s.start, s.end]);
+    let first = points.next()?;
+    Some(points.fold((first, first), |(min, max), p| {
+        (
+            Point::new(min.x.min(p.x), min.y.min(p.y)),
+            Point::new(max.x.max(p.x), max.y.max(p.y)),
+        )
+    }))
+}
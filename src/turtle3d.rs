@@ -0,0 +1,396 @@
+//! Turtle graphics in three dimensions, with the rotation symbols of The Algorithmic Beauty of
+//! Plants.
+//!
+//! The orientation of the turtle is a frame of three orthonormal vectors: its heading `H`, the
+//! direction to its left `L` and the direction up from its back `U`. Turning left and right
+//! rotates it around `U`, pitching around `L` and rolling around `H`. The default frame heads up
+//! the y axis with `U` pointing out of the xy plane, so drawings that only turn left and right
+//! match those of the 2D [`turtle`](crate::turtle).
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::parametric::Module;
+
+/// A command the 3D turtle understands, with an optional argument overriding the configured step
+/// length or turning angle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Moves forward, drawing a line.
+    Forward(Option<f64>),
+    /// Moves forward without drawing.
+    Move(Option<f64>),
+    /// Turns left around `U`, by an angle in degrees.
+    Left(Option<f64>),
+    /// Turns right around `U`, by an angle in degrees.
+    Right(Option<f64>),
+    /// Pitches down around `L`, by an angle in degrees.
+    PitchDown(Option<f64>),
+    /// Pitches up around `L`, by an angle in degrees.
+    PitchUp(Option<f64>),
+    /// Rolls left around `H`, by an angle in degrees.
+    RollLeft(Option<f64>),
+    /// Rolls right around `H`, by an angle in degrees.
+    RollRight(Option<f64>),
+    /// Turns by 180 degrees around `U`.
+    TurnAround,
+    /// Rolls around `H` until `L` is horizontal, perpendicular to [`Turtle::vertical`].
+    RollToHorizontal,
+    /// Sets the width of the following lines, or without an argument decreases it by
+    /// [`Turtle::width_decrement`].
+    Width(Option<f64>),
+    /// Saves the state of the turtle, to start a branch.
+    Push,
+    /// Restores the last saved state, to end a branch.
+    Pop,
+}
+
+/// Elements with a standard meaning as 3D turtle commands, see [`Turtle::interpret`].
+pub trait AsCommand {
+    fn as_command(&self) -> Option<Command>;
+}
+
+/// `F` draws a line, `f` moves, `+` and `-` turn left and right, `&` and `^` pitch down and up,
+/// `\` and `/` roll left and right, `|` turns around, `$` rolls to horizontal, `!` decreases the
+/// width, and `[` and `]` start and end a branch. All other characters are ignored.
+impl AsCommand for char {
+    fn as_command(&self) -> Option<Command> {
+        Some(match self {
+            'F' => Command::Forward(None),
+            'f' => Command::Move(None),
+            '+' => Command::Left(None),
+            '-' => Command::Right(None),
+            '&' => Command::PitchDown(None),
+            '^' => Command::PitchUp(None),
+            '\\' => Command::RollLeft(None),
+            '/' => Command::RollRight(None),
+            '|' => Command::TurnAround,
+            '$' => Command::RollToHorizontal,
+            '!' => Command::Width(None),
+            '[' => Command::Push,
+            ']' => Command::Pop,
+            _ => return None,
+        })
+    }
+}
+
+/// Like the symbols on their own, where the first parameter, if any, replaces the step length of
+/// `F` and `f`, the angle of the turns, pitches and rolls, and the width set by `!`.
+impl AsCommand for Module<char> {
+    fn as_command(&self) -> Option<Command> {
+        let argument = self.params.first().copied();
+        Some(match self.symbol.as_command()? {
+            Command::Forward(_) => Command::Forward(argument),
+            Command::Move(_) => Command::Move(argument),
+            Command::Left(_) => Command::Left(argument),
+            Command::Right(_) => Command::Right(argument),
+            Command::PitchDown(_) => Command::PitchDown(argument),
+            Command::PitchUp(_) => Command::PitchUp(argument),
+            Command::RollLeft(_) => Command::RollLeft(argument),
+            Command::RollRight(_) => Command::RollRight(argument),
+            Command::Width(_) => Command::Width(argument),
+            command => command,
+        })
+    }
+}
+
+/// A point or direction in space.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector {
+    pub const X: Vector = Vector::new(1.0, 0.0, 0.0);
+    pub const Y: Vector = Vector::new(0.0, 1.0, 0.0);
+    pub const Z: Vector = Vector::new(0.0, 0.0, 1.0);
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Vector { x, y, z }
+    }
+
+    pub fn dot(self, other: Vector) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vector) -> Vector {
+        Vector::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// The vector scaled to length 1, or `None` if it is too short to have a direction.
+    pub fn normalized(self) -> Option<Vector> {
+        let length = self.length();
+        (length > 1e-12).then(|| self * (1.0 / length))
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Vector {
+    type Output = Vector;
+
+    fn mul(self, factor: f64) -> Vector {
+        Vector::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// A line drawn by the turtle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Vector,
+    pub end: Vector,
+    pub width: f64,
+}
+
+/// Bends the heading of the turtle toward `direction` after every step, by an angle proportional
+/// to `susceptibility` and to the sine of the angle between them, as in The Algorithmic Beauty
+/// of Plants. The length of `direction` scales the effect as well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tropism {
+    pub direction: Vector,
+    pub susceptibility: f64,
+}
+
+impl Tropism {
+    pub fn new(direction: Vector, susceptibility: f64) -> Self {
+        Tropism {
+            direction,
+            susceptibility,
+        }
+    }
+}
+
+/// The configuration of a 3D turtle: how far it moves and turns, where it starts and how it is
+/// oriented.
+///
+/// ```
+/// use lsystem::turtle3d::{Turtle, Vector};
+///
+/// // up, then pitched down into the screen
+/// let segments = Turtle::new(1.0, 90.0).interpret(&['F', '&', 'F']);
+/// let end = segments[1].end;
+/// assert!((end - Vector::new(0.0, 1.0, -1.0)).length() < 1e-9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turtle {
+    pub step: f64,
+    /// The default turning angle, in degrees.
+    pub angle: f64,
+    pub origin: Vector,
+    /// The initial heading `H`.
+    pub heading: Vector,
+    /// The initial direction `U`, perpendicular to the heading.
+    pub up: Vector,
+    /// The direction opposite to gravity, which `$` keeps `L` perpendicular to.
+    pub vertical: Vector,
+    pub tropism: Option<Tropism>,
+    /// The initial width of lines.
+    pub width: f64,
+    /// How much `!` without an argument decreases the width.
+    pub width_decrement: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    position: Vector,
+    heading: Vector,
+    left: Vector,
+    up: Vector,
+    width: f64,
+}
+
+impl Turtle {
+    /// A turtle starting at the origin, heading up the y axis with its back toward the positive
+    /// z axis, without tropism, drawing lines of width 1.
+    pub fn new(step: f64, angle: f64) -> Self {
+        Turtle {
+            step,
+            angle,
+            origin: Vector::default(),
+            heading: Vector::Y,
+            up: Vector::Z,
+            vertical: Vector::Y,
+            tropism: None,
+            width: 1.0,
+            width_decrement: 0.1,
+        }
+    }
+
+    pub fn with_origin(mut self, origin: Vector) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Orients the turtle along `heading`, with its back toward `up`. Both are normalized, and
+    /// `up` is made perpendicular to `heading`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either vector is zero or they are parallel.
+    pub fn with_orientation(mut self, heading: Vector, up: Vector) -> Self {
+        let heading = heading.normalized().expect("the heading has no direction");
+        let left = up
+            .cross(heading)
+            .normalized()
+            .expect("the up vector is parallel to the heading");
+        self.heading = heading;
+        self.up = heading.cross(left);
+        self
+    }
+
+    pub fn with_vertical(mut self, vertical: Vector) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    pub fn with_tropism(mut self, tropism: Tropism) -> Self {
+        self.tropism = Some(tropism);
+        self
+    }
+
+    pub fn with_width(mut self, width: f64) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Draws `elements`, using their standard meaning as commands.
+    pub fn interpret<T: AsCommand>(&self, elements: &[T]) -> Vec<Segment> {
+        self.interpret_with(elements, T::as_command)
+    }
+
+    /// Draws `elements`, using `command` to look up the command for each of them. Elements
+    /// without a command are ignored, and so are branches that end without having started.
+    pub fn interpret_with<T>(
+        &self,
+        elements: &[T],
+        command: impl Fn(&T) -> Option<Command>,
+    ) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut state = State {
+            position: self.origin,
+            heading: self.heading,
+            left: self.up.cross(self.heading),
+            up: self.up,
+            width: self.width,
+        };
+        let mut stack = Vec::new();
+        for element in elements {
+            let Some(command) = command(element) else {
+                continue;
+            };
+            let angle = |angle: Option<f64>| angle.unwrap_or(self.angle).to_radians();
+            match command {
+                Command::Forward(distance) | Command::Move(distance) => {
+                    let start = state.position;
+                    state.position = start + state.heading * distance.unwrap_or(self.step);
+                    if let Command::Forward(_) = command {
+                        segments.push(Segment {
+                            start,
+                            end: state.position,
+                            width: state.width,
+                        });
+                    }
+                    if let Some(tropism) = self.tropism {
+                        state.bend(tropism);
+                    }
+                }
+                Command::Left(a) => {
+                    (state.heading, state.left) = rotate(state.heading, state.left, angle(a))
+                }
+                Command::Right(a) => {
+                    (state.heading, state.left) = rotate(state.heading, state.left, -angle(a))
+                }
+                Command::PitchDown(a) => {
+                    (state.heading, state.up) = rotate(state.heading, state.up, -angle(a))
+                }
+                Command::PitchUp(a) => {
+                    (state.heading, state.up) = rotate(state.heading, state.up, angle(a))
+                }
+                Command::RollLeft(a) => {
+                    (state.left, state.up) = rotate(state.left, state.up, -angle(a))
+                }
+                Command::RollRight(a) => {
+                    (state.left, state.up) = rotate(state.left, state.up, angle(a))
+                }
+                Command::TurnAround => {
+                    state.heading = -state.heading;
+                    state.left = -state.left;
+                }
+                Command::RollToHorizontal => {
+                    if let Some(left) = self.vertical.cross(state.heading).normalized() {
+                        state.left = left;
+                        state.up = state.heading.cross(left);
+                    }
+                }
+                Command::Width(Some(width)) => state.width = width,
+                Command::Width(None) => state.width = (state.width - self.width_decrement).max(0.0),
+                Command::Push => stack.push(state),
+                Command::Pop => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+            }
+        }
+        segments
+    }
+}
+
+impl State {
+    /// Rotates the frame around `H × T`, turning the heading toward the tropism vector `T`.
+    fn bend(&mut self, tropism: Tropism) {
+        let torque = self.heading.cross(tropism.direction);
+        let Some(axis) = torque.normalized() else {
+            return;
+        };
+        let angle = tropism.susceptibility * torque.length();
+        self.heading = rotate_around(self.heading, axis, angle);
+        self.left = rotate_around(self.left, axis, angle);
+        self.up = rotate_around(self.up, axis, angle);
+    }
+}
+
+/// Rotates two perpendicular unit vectors in their plane, turning `a` toward `b` by `angle`
+/// radians.
+fn rotate(a: Vector, b: Vector, angle: f64) -> (Vector, Vector) {
+    let (sin, cos) = angle.sin_cos();
+    (a * cos + b * sin, b * cos - a * sin)
+}
+
+/// Rotates `v` by `angle` radians around the unit vector `axis`, counterclockwise when looking
+/// against it.
+pub(crate) fn rotate_around(v: Vector, axis: Vector, angle: f64) -> Vector {
+    let (sin, cos) = angle.sin_cos();
+    v * cos + axis.cross(v) * sin + axis * (axis.dot(v) * (1.0 - cos))
+}
@@ -0,0 +1,88 @@
+use lsystem::analysis::Growth;
+use lsystem::deterministic::lsystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn assert_predicts_expansion(axiom: &[char], rules: &[(char, Vec<char>)], generations: u32) {
+    let growth = Growth::new(axiom, rules);
+    for n in 0..generations {
+        let expanded = lsystem(axiom.to_vec(), rules, n);
+        assert_eq!(growth.len(n), expanded.len() as u128, "generation {n}");
+        let counted: Vec<u128> = growth
+            .symbols()
+            .iter()
+            .map(|s| expanded.iter().filter(|e| *e == s).count() as u128)
+            .collect();
+        assert_eq!(growth.parikh(n), counted, "generation {n}");
+    }
+}
+
+#[test]
+fn fibonacci() {
+    let rules = vec![('A', chars("AB")), ('B', chars("A"))];
+    assert_predicts_expansion(&chars("A"), &rules, 20);
+
+    let growth = Growth::new(&chars("A"), &rules);
+    assert_eq!(growth.matrix(), [vec![1, 1], vec![1, 0]]);
+    let (mut a, mut b) = (1u128, 1u128);
+    for _ in 0..149 {
+        (a, b) = (b, a + b);
+    }
+    assert_eq!(growth.len(149), b);
+    assert!((growth.growth_rate() - (1.0 + 5f64.sqrt()) / 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn koch() {
+    let rules = vec![('F', chars("F+F-F-F+F"))];
+    assert_predicts_expansion(&chars("F-F-F-F"), &rules, 7);
+
+    let growth = Growth::new(&chars("F-F-F-F"), &rules);
+    assert_eq!(growth.symbols(), chars("F-+"));
+    assert_eq!(growth.count(30, &'F'), 4 * 5u128.pow(30));
+    assert_eq!(growth.count(30, &'x'), 0);
+    assert!((growth.growth_rate() - 5.0).abs() < 1e-9);
+}
+
+#[test]
+fn quadratic_koch_island() {
+    let rules = vec![('F', chars("F-F+F+FF-F-F+F"))];
+    assert_predicts_expansion(&chars("F-F-F-F"), &rules, 4);
+    assert!((Growth::new(&chars("F-F-F-F"), &rules).growth_rate() - 8.0).abs() < 1e-9);
+}
+
+#[test]
+fn growth_rate_only_considers_reachable_symbols() {
+    let rules = vec![('A', chars("AB")), ('C', chars("CCC"))];
+    let growth = Growth::new(&chars("A"), &rules);
+    assert_eq!(growth.len(100), 101);
+    assert!((growth.growth_rate() - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn vanishing_systems() {
+    let growth = Growth::new(&chars("AB"), &[('A', vec![]), ('B', chars("A"))]);
+    assert_eq!(growth.len(1), 1);
+    assert!(growth.is_empty(2));
+    assert_eq!(growth.growth_rate(), 0.0);
+    assert!(Growth::new(&[], &[('A', chars("AA"))]).is_empty(3));
+}
+
+#[test]
+fn counts_saturate() {
+    let growth = Growth::new(&chars("A"), &[('A', chars("AAAA"))]);
+    assert_eq!(growth.len(63), 1 << 126);
+    assert_eq!(growth.len(1000), u128::MAX);
+}
+
+#[test]
+fn empty_grammar() {
+    let growth: Growth<char> = Growth::new(&[], &[]);
+    assert!(growth.symbols().is_empty());
+    assert_eq!(growth.parikh(1), Vec::<u128>::new());
+    assert_eq!(growth.len(5), 0);
+    assert_eq!(growth.count(1, &'x'), 0);
+    assert_eq!(growth.growth_rate(), 0.0);
+}
@@ -0,0 +1,84 @@
+use lsystem::automaton::{automaton_lsystem, Automaton};
+use lsystem::context::complex_lsystem;
+use lsystem::limits::{Exceeded, Limits};
+use lsystem::stochastic::seeded_rng;
+use lsystem::LSystem;
+use rand::Rng;
+
+fn word(rng: &mut impl Rng, alphabet: u8, max_len: usize) -> Vec<u8> {
+    let len = rng.gen_range(0..=max_len);
+    (0..len).map(|_| rng.gen_range(0..alphabet)).collect()
+}
+
+#[test]
+fn behaves_like_complex_lsystem() {
+    let mut rng = seeded_rng(12);
+    for round in 0..2000 {
+        // small alphabets make overlapping and nested patterns likely
+        let alphabet = rng.gen_range(1..5);
+        let rules: Vec<(Vec<u8>, Vec<u8>)> = (0..rng.gen_range(0..10))
+            .map(|_| (word(&mut rng, alphabet, 5), word(&mut rng, alphabet, 4)))
+            .filter(|(original, _)| !original.is_empty())
+            .collect();
+        let axiom = word(&mut rng, alphabet, 12);
+        let automaton = Automaton::new(rules.clone());
+        for n in 0..4 {
+            assert_eq!(
+                automaton.expand(axiom.clone(), n),
+                complex_lsystem(axiom.clone(), &rules, n),
+                "round {round}: {rules:?} {axiom:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn first_rule_wins_regardless_of_length() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    let rules = vec![
+        (chars("BCD"), chars("1")),
+        (chars("AB"), chars("2")),
+        (chars("ABCDE"), chars("3")),
+        (chars("C"), chars("4")),
+        (chars("AB"), chars("never")),
+    ];
+    assert_eq!(automaton_lsystem(chars("ABCDE"), &rules, 1), chars("24DE"));
+    assert_eq!(automaton_lsystem(chars("XBCDE"), &rules, 1), chars("X1E"));
+    assert_eq!(
+        automaton_lsystem(chars("ABCDE"), &rules, 1),
+        complex_lsystem(chars("ABCDE"), &rules, 1)
+    );
+}
+
+#[test]
+fn without_rules_everything_is_constant() {
+    let automaton: Automaton<char> = Automaton::new(vec![]);
+    assert_eq!(automaton.expand(vec!['A', 'B'], 3), vec!['A', 'B']);
+    let mut out = vec![];
+    Automaton::new(vec![(vec![], vec!['X'])]).rewrite(&['A'], &mut out);
+    assert_eq!(out, vec!['A']);
+}
+
+#[test]
+fn works_as_a_rule_of_an_lsystem() {
+    let rules = vec![
+        (vec!['A', 'B'], vec!['C']),
+        (vec!['A'], vec!['A', 'B']),
+        (vec!['C'], vec!['A', 'C', 'A']),
+    ];
+    let automaton = Automaton::new(rules.clone());
+    let system = LSystem::new(vec!['A', 'B', 'A']).with_rule(automaton.clone());
+    assert_eq!(system.expand(6), automaton.expand(vec!['A', 'B', 'A'], 6));
+    assert_eq!(
+        system.generations().nth(4).unwrap(),
+        complex_lsystem(vec!['A', 'B', 'A'], &rules, 4)
+    );
+    let error = system
+        .try_expand(100, &Limits::new().max_symbols(1000))
+        .unwrap_err();
+    assert_eq!(error.exceeded, Exceeded::Symbols);
+    assert_eq!(
+        error.last,
+        automaton.expand(vec!['A', 'B', 'A'], error.generation)
+    );
+}
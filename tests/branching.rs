@@ -0,0 +1,72 @@
+use lsystem::branching::Branching;
+use lsystem::context::ContextRule;
+use lsystem::deterministic::SymbolRule;
+use lsystem::LSystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn string(elements: Vec<char>) -> String {
+    elements.into_iter().collect()
+}
+
+/// Rewrites `axiom` once with `left < original > right -> X`, and returns it.
+fn rewrite(
+    axiom: &str,
+    left: &str,
+    original: char,
+    right: &str,
+    branching: Branching<char>,
+) -> String {
+    let rule = ContextRule::new(chars(left), vec![original], chars(right), chars("X"))
+        .with_branching(branching);
+    string(LSystem::new(chars(axiom)).with_rule(rule).expand(1))
+}
+
+fn brackets() -> Branching<char> {
+    Branching::brackets().ignoring(['+', '-'])
+}
+
+#[test]
+fn left_context_is_the_path_from_the_root() {
+    assert_eq!(rewrite("A[B]C", "A", 'C', "", brackets()), "A[B]X");
+    assert_eq!(rewrite("A[B]C", "B", 'C', "", brackets()), "A[B]C");
+    assert_eq!(rewrite("A[B]C", "A", 'B', "", brackets()), "A[X]C");
+    assert_eq!(rewrite("A[B[C]D]E", "AB", 'D', "", brackets()), "A[B[C]X]E");
+    assert_eq!(rewrite("A[B[C]D]E", "AB", 'C', "", brackets()), "A[B[X]D]E");
+    assert_eq!(rewrite("A[B[C]D]E", "A", 'E', "", brackets()), "A[B[C]D]X");
+    assert_eq!(rewrite("[A]B", "A", 'B', "", brackets()), "[A]B");
+}
+
+#[test]
+fn right_context_continues_into_branches() {
+    assert_eq!(rewrite("A[B]C", "", 'A', "C", brackets()), "X[B]C");
+    assert_eq!(rewrite("A[B]C", "", 'A', "[B]C", brackets()), "X[B]C");
+    assert_eq!(rewrite("A[B][C]D", "", 'A', "[C]", brackets()), "A[B][C]D");
+    assert_eq!(rewrite("A[B]C", "", 'A', "[B", brackets()), "X[B]C");
+    assert_eq!(rewrite("A[B]C", "", 'B', "C", brackets()), "A[B]C");
+    assert_eq!(rewrite("A[B[C]D]E", "", 'B', "D", brackets()), "A[X[C]D]E");
+    assert_eq!(rewrite("A[BC]D", "", 'A', "[B]D", brackets()), "X[BC]D");
+    assert_eq!(rewrite("A[B", "", 'A', "C", brackets()), "A[B");
+}
+
+#[test]
+fn ignored_symbols_are_invisible() {
+    assert_eq!(rewrite("A+-B", "A", 'B', "", brackets()), "A+-X");
+    assert_eq!(rewrite("A+-B", "A", 'B', "", Branching::brackets()), "A+-B");
+    assert_eq!(rewrite("A[+B]-C", "", 'A', "[B]C", brackets()), "X[+B]-C");
+    assert_eq!(rewrite("A[+B]-C", "", 'A', "C", brackets()), "X[+B]-C");
+}
+
+#[test]
+fn signal_travels_up_all_branches() {
+    let signal =
+        ContextRule::new(chars("b"), chars("a"), vec![], chars("b")).with_branching(brackets());
+    let system = LSystem::new(chars("b[a]a[+a]a"))
+        .with_rule(signal)
+        .with_rule(SymbolRule::new('b', chars("a")));
+    let generations: Vec<String> = system.generations().take(3).map(string).collect();
+    assert_eq!(generations, ["b[a]a[+a]a", "a[b]b[+a]a", "a[a]a[+b]b"]);
+    assert!(system.stream(1).is_none());
+}
@@ -0,0 +1,105 @@
+use lsystem::context::{ContextRule, SliceRule};
+use lsystem::deterministic::SymbolRule;
+use lsystem::LSystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn string(elements: Vec<char>) -> String {
+    elements.into_iter().collect()
+}
+
+#[test]
+fn signal_propagation() {
+    // The Algorithmic Beauty of Plants, section 1.8
+    let system = LSystem::new(chars("baaaaaaaa"))
+        .with_rule(ContextRule::new(chars("b"), chars("a"), vec![], chars("b")))
+        .with_rule(SymbolRule::new('b', chars("a")));
+    let generations: Vec<String> = system.generations().take(4).map(string).collect();
+    assert_eq!(
+        generations,
+        ["baaaaaaaa", "abaaaaaaa", "aabaaaaaa", "aaabaaaaa"]
+    );
+}
+
+#[test]
+fn context_is_not_consumed() {
+    // replace B only when preceded by A, and A by X independently
+    let system = LSystem::new(chars("ABBAB"))
+        .with_rule(ContextRule::new(chars("A"), chars("B"), vec![], chars("C")))
+        .with_rule(SymbolRule::new('A', chars("X")));
+    assert_eq!(string(system.expand(1)), "XCBXC");
+
+    // a slice rule on the other hand consumes the A
+    let consuming = LSystem::new(chars("ABBAB")).with_rule(SliceRule::new(chars("AB"), chars("C")));
+    assert_eq!(string(consuming.expand(1)), "CBC");
+}
+
+#[test]
+fn all_positions_are_rewritten_simultaneously() {
+    let system = LSystem::new(chars("aaaa")).with_rule(ContextRule::new(
+        chars("a"),
+        chars("a"),
+        vec![],
+        chars("b"),
+    ));
+    assert_eq!(string(system.expand(1)), "abbb");
+    assert_eq!(string(system.expand(2)), "abbb");
+}
+
+#[test]
+fn left_and_right_context() {
+    let system = LSystem::new(chars("xABCyABDzBC"))
+        .with_rule(ContextRule::new(
+            chars("xA"),
+            chars("B"),
+            chars("C"),
+            chars("1"),
+        ))
+        .with_rule(ContextRule::new(
+            chars("A"),
+            chars("B"),
+            chars("D"),
+            chars("22"),
+        ))
+        .with_rule(ContextRule::new(vec![], chars("BC"), chars(""), chars("3")));
+    assert_eq!(string(system.expand(1)), "xA1CyA22Dz3");
+}
+
+#[test]
+fn context_at_the_edges_does_not_match() {
+    let system = LSystem::new(chars("BAB")).with_rule(ContextRule::new(
+        chars("A"),
+        chars("B"),
+        chars("A"),
+        chars("X"),
+    ));
+    assert_eq!(string(system.expand(1)), "BAB");
+}
+
+#[test]
+fn streams_with_left_context() {
+    let system = LSystem::new(chars("baaaaaaab"))
+        .with_rule(ContextRule::new(chars("b"), chars("a"), vec![], chars("b")))
+        .with_rule(ContextRule::new(
+            chars("ab"),
+            chars("a"),
+            chars("a"),
+            chars("ca"),
+        ))
+        .with_rule(ContextRule::new(
+            vec![],
+            chars("b"),
+            chars("c"),
+            chars("ab"),
+        ))
+        .with_rule(SymbolRule::new('b', chars("a")));
+    for n in 0..12 {
+        assert_eq!(
+            system.stream(n).unwrap().collect::<Vec<_>>(),
+            system.expand(n),
+            "generation {n}"
+        );
+    }
+}
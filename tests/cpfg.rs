@@ -0,0 +1,148 @@
+use lsystem::branching::Branching;
+use lsystem::context::ContextRule;
+use lsystem::cpfg;
+use lsystem::deterministic::SymbolRule;
+use lsystem::grammar::{Grammar, GrammarError};
+use lsystem::parametric::Module;
+use lsystem::LSystem;
+
+fn fixture(name: &str) -> Grammar {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    let source = std::fs::read_to_string(&path).unwrap();
+    cpfg::import(&source).unwrap_or_else(|error| panic!("{path}: {error}"))
+}
+
+/// The generation the fixture is meant to be expanded to.
+fn derive(name: &str, seed: u64) -> Vec<Module<char>> {
+    let grammar = fixture(name);
+    let iterations = grammar.iterations.unwrap();
+    grammar.into_lsystem().expand_seeded(iterations, seed)
+}
+
+fn symbols(modules: &[Module<char>]) -> String {
+    modules.iter().map(|m| m.symbol).collect()
+}
+
+fn count(modules: &[Module<char>], symbol: char) -> usize {
+    modules.iter().filter(|m| m.symbol == symbol).count()
+}
+
+fn error(source: &str) -> GrammarError {
+    match cpfg::import(source) {
+        Ok(_) => panic!("{source:?} should not import"),
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn bracketed_fixtures() {
+    // the number of modules and of forward steps in the final generation
+    for (name, len, forward_steps) in [
+        ("abop_1_24a.l", 7811, 3125),
+        ("abop_1_24d.l", 13956, 4118),
+        ("abop_1_24f.l", 6263, 1488),
+    ] {
+        let generation = derive(name, 0);
+        assert_eq!(generation.len(), len, "{name}");
+        assert_eq!(count(&generation, 'F'), forward_steps, "{name}");
+    }
+    assert_eq!(fixture("abop_1_24d.l").constants["delta"], 20.0);
+}
+
+#[test]
+fn stochastic_fixture() {
+    let grammar = fixture("abop_1_27.l");
+    assert_eq!(grammar.rules.len(), 1);
+    let probabilities: Vec<f64> = grammar.rules[0].successors().map(|(_, p)| p).collect();
+    for (probability, expected) in probabilities.iter().zip([0.33, 0.33, 0.34]) {
+        assert!((probability - expected).abs() < 1e-9);
+    }
+    assert_eq!(derive("abop_1_27.l", 3), derive("abop_1_27.l", 3));
+    let generation = derive("abop_1_27.l", 3);
+    // every F is replaced by 3 or 5 of them
+    let forward_steps = count(&generation, 'F');
+    assert!((3usize.pow(5)..=5usize.pow(5)).contains(&forward_steps));
+    assert_eq!(count(&generation, '['), count(&generation, ']'));
+}
+
+#[test]
+fn context_sensitive_fixture_matches_hand_built_rules() {
+    let branching = || Branching::brackets().ignoring(['+', '-', 'F']);
+    let rule = |left: char, original: char, right: char, replacement: &str| {
+        ContextRule::new(
+            vec![left],
+            vec![original],
+            vec![right],
+            replacement.chars().collect(),
+        )
+        .with_branching(branching())
+    };
+    let system = LSystem::new("F1F1F1".chars().collect())
+        .with_rule(rule('0', '0', '0', "0"))
+        .with_rule(rule('0', '0', '1', "1[+F1F1]"))
+        .with_rule(rule('0', '1', '0', "1"))
+        .with_rule(rule('0', '1', '1', "1"))
+        .with_rule(rule('1', '0', '0', "0"))
+        .with_rule(rule('1', '0', '1', "1F1"))
+        .with_rule(rule('1', '1', '0', "1"))
+        .with_rule(rule('1', '1', '1', "0"))
+        .with_rule(SymbolRule::new('+', vec!['-']))
+        .with_rule(SymbolRule::new('-', vec!['+']));
+    let expected: String = system.expand(30).into_iter().collect();
+
+    let generation = derive("abop_1_31a.l", 0);
+    assert_eq!(symbols(&generation), expected);
+    assert_eq!(generation.len(), 6748);
+}
+
+#[test]
+fn parametric_fixtures() {
+    let generation: String = derive("abop_1_10.l", 0)
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    assert_eq!(generation, "CB(1)A(8,7)");
+
+    let tree = derive("abop_2_6.l", 0);
+    assert_eq!(tree.len(), 7162);
+    // every apex forks into two, and every internode keeps its length
+    assert_eq!(count(&tree, 'F'), 1023);
+    assert_eq!(tree[0], Module::new('!', vec![10.0]));
+    assert_eq!(tree[1], Module::new('F', vec![1.0]));
+    assert_eq!(tree[3], Module::new('&', vec![45.0]));
+}
+
+#[test]
+fn comments_keep_positions() {
+    let error = error("/* a\n comment */ Axiom: F\nF --> G(x)");
+    assert_eq!((error.line, error.column), (3, 9));
+    assert_eq!(error.message, "unknown parameter `x`");
+}
+
+#[test]
+fn unsupported_features_are_errors() {
+    assert_eq!(
+        error("Axiom: F\nhomomorphism\nF --> G").message,
+        "`homomorphism` is not supported"
+    );
+    assert_eq!(
+        error("consider: FG\nAxiom: F").message,
+        "`consider` is not supported"
+    );
+    assert_eq!(
+        error("#define f(x) x\nAxiom: F").message,
+        "macros with parameters are not supported"
+    );
+    assert_eq!(error("#include \"a.h\"\nAxiom: F").line, 1);
+    assert_eq!(
+        error("#define N many\nAxiom: F").message,
+        "expected a constant expression"
+    );
+}
+
+#[test]
+fn everything_after_endlsystem_is_ignored() {
+    let grammar = cpfg::import("Axiom: F\nF --> FF\nendlsystem\nhomomorphism\n").unwrap();
+    assert_eq!(grammar.rules.len(), 1);
+    assert_eq!(grammar.iterations, None);
+}
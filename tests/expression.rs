@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use lsystem::expression::{Expr, ParseError, ParseErrorKind, MAX_DEPTH};
+use lsystem::grammar::Grammar;
+use lsystem::parametric::{
+    parametric_lsystem, Condition, Env, Expression, Module, ParametricRule, Pattern, Template,
+};
+use lsystem::stochastic::seeded_rng;
+
+fn eval(source: &str) -> f64 {
+    let mut env = Env::new();
+    env.bind("x", 2.0);
+    env.bind("y", 3.0);
+    Expr::parse(source)
+        .unwrap()
+        .evaluate(&env, &mut seeded_rng(0))
+}
+
+fn error(source: &str) -> ParseError {
+    Expr::parse(source).unwrap_err()
+}
+
+#[test]
+fn arithmetic_and_precedence() {
+    assert_eq!(eval("1 + 2 * 3"), 7.0);
+    assert_eq!(eval("(1 + 2) * 3"), 9.0);
+    assert_eq!(eval("x * y - 1"), 5.0);
+    assert_eq!(eval("10 - 4 - 3"), 3.0);
+    assert_eq!(eval("7 % 4"), 3.0);
+    assert_eq!(eval("-2 ^ 2"), -4.0);
+    assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+    assert_eq!(eval(".5 + 1e1 + 2.5E-1"), 10.75);
+    assert_eq!(eval("- -x"), 2.0);
+}
+
+#[test]
+fn comparisons_and_logic() {
+    assert_eq!(eval("x < y"), 1.0);
+    assert_eq!(eval("x >= y"), 0.0);
+    assert_eq!(eval("x == 2 && y != 2"), 1.0);
+    assert_eq!(eval("x > 5 || !(y > 5)"), 1.0);
+    assert_eq!(eval("1 + 1 == 2"), 1.0);
+    assert_eq!(eval("x < y == 1"), 1.0);
+}
+
+#[test]
+fn functions() {
+    assert_eq!(eval("sqrt(16)"), 4.0);
+    assert_eq!(eval("min(x, y, 1)"), 1.0);
+    assert_eq!(eval("max(x, y)"), 3.0);
+    assert_eq!(eval("sin(0) + cos(0)"), 1.0);
+    assert_eq!(eval("pow(x, y)"), 8.0);
+    assert_eq!(eval("floor(2.7) + ceil(0.2) + abs(-1)"), 4.0);
+}
+
+#[test]
+fn random_functions_draw_from_the_given_rng() {
+    for source in ["rand()", "rand(2, 4)", "ran(5)", "nran(0, 1)"] {
+        let expr = Expr::parse(source).unwrap();
+        assert_eq!(expr.constant(), None);
+        let first = expr.evaluate(&Env::new(), &mut seeded_rng(1));
+        let second = expr.evaluate(&Env::new(), &mut seeded_rng(1));
+        assert_eq!(first, second);
+    }
+    let mut rng = seeded_rng(2);
+    let uniform = Expr::parse("rand(2, 4)").unwrap();
+    for _ in 0..100 {
+        assert!((2.0..4.0).contains(&uniform.evaluate(&Env::new(), &mut rng)));
+    }
+}
+
+#[test]
+fn constants_are_substituted() {
+    let constants = HashMap::from([("R".to_string(), 2.0), ("x".to_string(), 10.0)]);
+    let expr = Expr::parse_with_constants("R * sqrt(4) + x", &constants).unwrap();
+    assert_eq!(expr.constant(), Some(14.0));
+    assert_eq!(Expr::parse("a + b * a").unwrap().variables(), ["a", "b"]);
+}
+
+#[test]
+fn unbound_names_are_nan() {
+    let expr = Expr::parse("z + 1").unwrap();
+    assert!(expr.evaluate(&Env::new(), &mut seeded_rng(0)).is_nan());
+    assert!(!expr.holds(&Env::new(), &mut seeded_rng(0)));
+}
+
+#[test]
+fn errors_report_their_position() {
+    assert_eq!(
+        error("1 + $"),
+        ParseError {
+            position: 4,
+            kind: ParseErrorKind::UnexpectedChar('$'),
+        }
+    );
+    assert_eq!(error("(1 + 2").position, 6);
+    assert_eq!(error("1 + * 2").position, 4);
+    assert_eq!(error("1 2").position, 2);
+    assert_eq!(error("x = 2").position, 2);
+    assert_eq!(
+        error("1..2").kind,
+        ParseErrorKind::InvalidNumber("1..2".into())
+    );
+    assert_eq!(
+        error("2 * foo(1)"),
+        ParseError {
+            position: 4,
+            kind: ParseErrorKind::UnknownFunction("foo".into()),
+        }
+    );
+    assert_eq!(
+        error("max()").kind,
+        ParseErrorKind::Arity {
+            function: "max".into(),
+            expected: "at least 1",
+            found: 0,
+        }
+    );
+    assert_eq!(
+        error("(1 + 2").to_string(),
+        "expected `)`, found the end of the expression at position 6"
+    );
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    // on a thread with the default stack size of spawned threads
+    std::thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(|| {
+            let minuses = "-".repeat(5000) + "1";
+            assert_eq!(
+                error(&minuses),
+                ParseError {
+                    position: MAX_DEPTH,
+                    kind: ParseErrorKind::TooDeep,
+                }
+            );
+            let parentheses = "(".repeat(5000) + "1" + &")".repeat(5000);
+            assert_eq!(error(&parentheses).kind, ParseErrorKind::TooDeep);
+            assert_eq!(error(&"1+".repeat(5000)).kind, ParseErrorKind::TooDeep);
+            let source = format!(
+                "axiom: A(1)\nA(x) -> A({}x{})",
+                "(".repeat(5000),
+                ")".repeat(5000)
+            );
+            assert!(Grammar::parse(&source).is_err());
+
+            assert_eq!(eval(&("-".repeat(MAX_DEPTH - 2) + "1")), 1.0);
+            assert_eq!(eval(&("1+".repeat(MAX_DEPTH - 1) + "1")), MAX_DEPTH as f64);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn drives_parametric_rules() {
+    // A(t) : t > 3 -> F(t*0.5)A(0), A(t) -> A(t+1)
+    let rules = [
+        ParametricRule::new(
+            Pattern::new('A', ["t"]),
+            vec![
+                Template::new('F').param(Expr::parse("t * 0.5").unwrap()),
+                Template::new('A').param(Expr::parse("0").unwrap()),
+            ],
+        )
+        .with_condition(Expr::parse("t > 3").unwrap()),
+        ParametricRule::new(
+            Pattern::new('A', ["t"]),
+            vec![Template::new('A').param(Expr::parse("t + 1").unwrap())],
+        ),
+    ];
+    let generation = parametric_lsystem(vec![Module::new('A', vec![3.0])], &rules, 3);
+    let generation: String = generation.iter().map(|m| m.to_string()).collect();
+    assert_eq!(generation, "F(2)A(1)");
+}
@@ -0,0 +1,42 @@
+use lsystem::deterministic::{lsystem, SymbolRule};
+use lsystem::stochastic::ChanceRule;
+use lsystem::LSystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn fibonacci() -> LSystem<char> {
+    LSystem::new(chars("A"))
+        .with_rule(SymbolRule::new('A', chars("AB")))
+        .with_rule(SymbolRule::new('B', chars("A")))
+}
+
+#[test]
+fn yields_the_axiom_first_and_then_every_generation() {
+    let rules = vec![('A', chars("AB")), ('B', chars("A"))];
+    let system = fibonacci();
+    for (n, generation) in system.generations().take(15).enumerate() {
+        assert_eq!(generation, lsystem(chars("A"), &rules, n as u32));
+        assert_eq!(generation, system.expand(n as u32));
+    }
+}
+
+#[test]
+fn borrowing_tracks_the_generation_number() {
+    let system = fibonacci();
+    let mut generations = system.generations();
+    assert_eq!(generations.generation(), None);
+    assert_eq!(generations.next_ref(), chars("A"));
+    assert_eq!(generations.generation(), Some(0));
+    assert_eq!(generations.next_ref(), chars("AB"));
+    assert_eq!(generations.next(), Some(chars("ABA")));
+    assert_eq!(generations.generation(), Some(2));
+}
+
+#[test]
+fn stochastic_generations_grow_from_each_other() {
+    let system = LSystem::new(chars("A")).with_rule(ChanceRule::new(chars("A"), chars("AA"), 0.5));
+    let lengths: Vec<usize> = system.generations().take(12).map(|g| g.len()).collect();
+    assert!(lengths.windows(2).all(|w| w[0] <= w[1] && w[1] <= 2 * w[0]));
+}
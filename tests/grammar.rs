@@ -0,0 +1,141 @@
+use lsystem::grammar::{Grammar, GrammarError};
+use lsystem::parametric::Module;
+
+fn string(modules: &[Module<char>]) -> String {
+    modules.iter().map(|m| m.to_string()).collect()
+}
+
+fn expand(source: &str, iterations: u32) -> String {
+    let grammar: Grammar = source.parse().unwrap();
+    string(&grammar.into_lsystem().expand(iterations))
+}
+
+fn error(source: &str) -> GrammarError {
+    match Grammar::parse(source) {
+        Ok(_) => panic!("{source:?} should not parse"),
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn plain_symbols() {
+    let fibonacci = "axiom: A\nA -> AB\nB -> A\n";
+    assert_eq!(expand(fibonacci, 5), "ABAABABAABAAB");
+    assert_eq!(expand("axiom: AB\nA ->\n", 1), "B");
+}
+
+#[test]
+fn comments_constants_and_directives() {
+    let grammar: Grammar = "
+        # growth by a constant factor
+        const R = 1.5
+        const START = R * 2   # constants can use earlier constants
+        iterations: 2
+        axiom: F(START)
+        F(x) -> F(x * R) + F(x)  # the successor can contain whitespace
+    "
+    .parse()
+    .unwrap();
+    assert_eq!(grammar.iterations, Some(2));
+    assert_eq!(grammar.constants["START"], 3.0);
+    assert_eq!(string(&grammar.into_lsystem().expand(1)), "F(4.5)+F(3)");
+}
+
+#[test]
+fn context_follows_branches_and_ignores_symbols() {
+    let signal = "
+        ignore: +-
+        axiom: b[+a]a[-a]a
+        b < a -> b
+        b -> a
+    ";
+    assert_eq!(expand(signal, 1), "a[+b]b[-a]a");
+    assert_eq!(expand(signal, 2), "a[+a]a[-b]b");
+    let parameters = "
+        axiom: A(1)B(2)C(3)
+        A(x) < B(y) > C(z) : x < z -> B(x + y + z)
+    ";
+    assert_eq!(expand(parameters, 1), "A(1)B(6)C(3)");
+}
+
+#[test]
+fn stochastic_alternatives() {
+    let source = "
+        axiom: F
+        F -> F[+F]F : 1
+        F -> F[-F]F : 1
+        F -> FF : 2
+        G -> G
+    ";
+    let grammar = Grammar::parse(source).unwrap();
+    assert_eq!(grammar.rules.len(), 2);
+    let probabilities: Vec<f64> = grammar.rules[0].successors().map(|(_, p)| p).collect();
+    assert_eq!(probabilities, [0.25, 0.25, 0.5]);
+
+    let system = grammar.into_lsystem();
+    assert_eq!(system.expand_seeded(4, 7), system.expand_seeded(4, 7));
+    let mut seen = Vec::new();
+    for seed in 0..50 {
+        let generation = string(&system.expand_seeded(1, seed));
+        assert!(["F[+F]F", "F[-F]F", "FF"].contains(&generation.as_str()));
+        if !seen.contains(&generation) {
+            seen.push(generation);
+        }
+    }
+    assert_eq!(seen.len(), 3);
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    let missing_arrow = error("axiom: A\nA(x) AB");
+    assert_eq!((missing_arrow.line, missing_arrow.column), (2, 8));
+    assert_eq!(missing_arrow.message, "expected `->`");
+
+    let unknown = error("axiom: A\nA(x) -> A(y)");
+    assert_eq!((unknown.line, unknown.column), (2, 11));
+    assert_eq!(unknown.message, "unknown parameter `y`");
+
+    let bad_expression = error("axiom: A\n  A(x) -> A(x + * 2)");
+    assert_eq!((bad_expression.line, bad_expression.column), (2, 17));
+
+    let unclosed = error("axiom: A(1");
+    assert_eq!(
+        (unclosed.column, unclosed.message.as_str()),
+        (9, "unclosed `(`")
+    );
+
+    let name = error("axiom: A\nA(1) -> A");
+    assert_eq!(
+        (name.column, name.message.as_str()),
+        (3, "expected a parameter name")
+    );
+
+    let predecessor = error("axiom: A\nAB -> A");
+    assert_eq!(
+        predecessor.message,
+        "expected a single module as the predecessor"
+    );
+
+    let probability = error("axiom: A\nA(x) -> A : x");
+    assert_eq!(probability.message, "expected a constant expression");
+
+    let weights = error("axiom: A\nA -> B : 0\nA -> C : 0");
+    assert_eq!((weights.line, weights.column), (2, 10));
+    assert_eq!(weights.message, "all weights are zero");
+
+    let axiom = error("A -> B\nB -> A\n");
+    assert_eq!((axiom.line, axiom.column), (2, 7));
+    assert_eq!(axiom.snippet, "B -> A");
+    assert_eq!(axiom.message, "the grammar has no axiom");
+    assert_eq!((error("").line, error("").column), (1, 1));
+    assert_eq!(error("axiom: A\naxiom: B").line, 2);
+    assert_eq!(error("axiom: A\niterations: many").column, 13);
+}
+
+#[test]
+fn errors_display_a_snippet() {
+    assert_eq!(
+        error("axiom: A\nA(x) -> A(y)").to_string(),
+        "unknown parameter `y` at line 2, column 11\n  A(x) -> A(y)\n            ^"
+    );
+}
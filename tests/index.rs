@@ -0,0 +1,81 @@
+use lsystem::deterministic::lsystem;
+use lsystem::index::GenerationIndex;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+#[test]
+fn agrees_with_expansion() {
+    let rules = vec![
+        ('F', chars("F+G")),
+        ('G', chars("F-G")),
+        ('X', vec![]),
+        ('F', chars("ignored")),
+    ];
+    let axiom = chars("FXG");
+    let index = GenerationIndex::new(&axiom, &rules);
+    for n in 0..10 {
+        let expanded = lsystem(axiom.clone(), &rules, n);
+        assert_eq!(index.len(n), expanded.len() as u128);
+        for (k, elem) in expanded.iter().enumerate() {
+            assert_eq!(index.get(n, k as u128), Some(*elem));
+        }
+        assert_eq!(index.get(n, expanded.len() as u128), None);
+        for start in 0..expanded.len() {
+            let end = (start + 7).min(expanded.len());
+            assert_eq!(
+                index.slice(n, start as u128..end as u128),
+                expanded[start..end]
+            );
+        }
+    }
+}
+
+#[test]
+fn slices_are_truncated_to_the_generation() {
+    let index = GenerationIndex::new(&chars("A"), &[('A', chars("AB")), ('B', chars("A"))]);
+    assert_eq!(index.slice(3, 2..100), chars("AAB"));
+    assert_eq!(index.slice(3, 50..100), vec![]);
+    assert_eq!(index.slice(3, 4..4), vec![]);
+}
+
+#[test]
+fn looks_into_astronomically_large_generations() {
+    let rules = vec![('A', chars("AB")), ('B', chars("A"))];
+    let index = GenerationIndex::new(&chars("A"), &rules);
+    // every generation of the fibonacci word is a prefix of the next one
+    let small = lsystem(chars("A"), &rules, 15);
+    assert!(index.len(150) > u64::MAX as u128);
+    assert_eq!(index.slice(150, 0..small.len() as u128), small);
+
+    // the thue-morse sequence: the k-th element is B exactly if k has an odd number of ones
+    let index = GenerationIndex::new(&chars("A"), &[('A', chars("AB")), ('B', chars("BA"))]);
+    for k in [
+        0,
+        1,
+        12345,
+        u64::MAX as u128,
+        (1 << 120) + 7,
+        (1 << 127) - 1,
+    ] {
+        let expected = if k.count_ones() % 2 == 0 { 'A' } else { 'B' };
+        assert_eq!(index.get(127, k), Some(expected));
+    }
+}
+
+#[test]
+fn lengths_saturate() {
+    let index = GenerationIndex::new(&chars("A"), &[('A', chars("AAAA"))]);
+    assert_eq!(index.len(63), 1 << 126);
+    assert_eq!(index.len(64), u128::MAX);
+    assert_eq!(index.get(64, u128::MAX - 1), Some('A'));
+    assert!(!index.is_empty(64));
+}
+
+#[test]
+fn descends_a_million_generations() {
+    let index = GenerationIndex::new(&chars("A"), &[('A', chars("AB")), ('B', chars("A"))]);
+    assert_eq!(index.slice(1_000_000, 0..8), chars("ABAABABA"));
+    assert_eq!(index.get(1_000_000, 1 << 100), index.get(200, 1 << 100));
+}
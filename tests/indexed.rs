@@ -0,0 +1,77 @@
+use lsystem::context::{complex_lsystem, indexed_complex_lsystem, PatternTrie};
+use lsystem::deterministic::{indexed_lsystem, lsystem, IndexedRules};
+use lsystem::stochastic::seeded_rng;
+use lsystem::LSystem;
+use rand::Rng;
+
+fn word(rng: &mut impl Rng, max_len: usize) -> Vec<u8> {
+    let len = rng.gen_range(0..=max_len);
+    (0..len).map(|_| rng.gen_range(0..6)).collect()
+}
+
+#[test]
+fn indexed_rules_behave_like_lsystem() {
+    let mut rng = seeded_rng(11);
+    for _ in 0..200 {
+        let rules: Vec<(u8, Vec<u8>)> = (0..rng.gen_range(0..8))
+            .map(|_| (rng.gen_range(0..6), word(&mut rng, 3)))
+            .collect();
+        let axiom = word(&mut rng, 5);
+        for n in 0..5 {
+            assert_eq!(
+                indexed_lsystem(axiom.clone(), &rules, n),
+                lsystem(axiom.clone(), &rules, n),
+                "{rules:?} {axiom:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn pattern_trie_behaves_like_complex_lsystem() {
+    let mut rng = seeded_rng(12);
+    for _ in 0..300 {
+        let rules: Vec<(Vec<u8>, Vec<u8>)> = (0..rng.gen_range(0..8))
+            .map(|_| (word(&mut rng, 3), word(&mut rng, 3)))
+            .filter(|(original, _)| !original.is_empty())
+            .collect();
+        let axiom = word(&mut rng, 8);
+        for n in 0..5 {
+            assert_eq!(
+                indexed_complex_lsystem(axiom.clone(), &rules, n),
+                complex_lsystem(axiom.clone(), &rules, n),
+                "{rules:?} {axiom:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn earlier_rules_win_over_longer_patterns() {
+    let rules = vec![
+        (vec!['A'], vec!['x']),
+        (vec!['A', 'B'], vec!['y']),
+        (vec!['B', 'C'], vec!['z']),
+        (vec!['B'], vec!['w']),
+        (vec!['A'], vec!['n']),
+    ];
+    let axiom: Vec<char> = "ABCBB".chars().collect();
+    let expected: Vec<char> = "xzww".chars().collect();
+    assert_eq!(indexed_complex_lsystem(axiom.clone(), &rules, 1), expected);
+    assert_eq!(complex_lsystem(axiom, &rules, 1), expected);
+}
+
+#[test]
+fn indexed_rules_mix_with_other_rules() {
+    let system = LSystem::new(vec!['A', 'B', 'C'])
+        .with_rule(PatternTrie::new([(vec!['A', 'B'], vec!['D'])]))
+        .with_rule(IndexedRules::new([('B', vec!['E']), ('C', vec!['F', 'C'])]));
+    assert_eq!(system.expand(2), vec!['D', 'F', 'F', 'C']);
+    assert_eq!(
+        system.stream(2).unwrap().collect::<Vec<_>>(),
+        system.expand(2)
+    );
+    let rules = IndexedRules::new([('B', vec!['E']), ('B', vec!['G'])]);
+    assert_eq!(rules.get(&'B'), Some(&['E'][..]));
+    assert_eq!(rules.get(&'A'), None);
+}
@@ -0,0 +1,134 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lsystem::deterministic::SymbolRule;
+use lsystem::functional::{try_arbitrary_lsystem, FnRule};
+use lsystem::limits::{CancellationToken, Exceeded, Limits};
+use lsystem::stochastic::try_random_lsystem;
+use lsystem::LSystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn doubling() -> LSystem<char> {
+    LSystem::new(chars("A")).with_rule(SymbolRule::new('A', chars("AA")))
+}
+
+#[test]
+fn within_limits_expands_normally() {
+    let limits = Limits::new().max_symbols(1 << 10).max_bytes(8 << 10);
+    assert_eq!(
+        doubling().try_expand(10, &limits),
+        Ok(doubling().expand(10))
+    );
+    assert_eq!(
+        doubling().try_expand(3, &Limits::new()),
+        Ok(chars("AAAAAAAA"))
+    );
+}
+
+#[test]
+fn max_symbols_keeps_the_last_complete_generation() {
+    let error = doubling()
+        .try_expand(100, &Limits::new().max_symbols(1000))
+        .unwrap_err();
+    assert_eq!(error.exceeded, Exceeded::Symbols);
+    assert_eq!(error.generation, 9);
+    assert_eq!(error.last, doubling().expand(9));
+    assert_eq!(
+        error.to_string(),
+        "expansion stopped after generation 9: maximum number of symbols exceeded"
+    );
+}
+
+#[test]
+fn max_bytes_counts_the_element_size() {
+    // a char takes four bytes, and the generations before and after a step are both held
+    let error = doubling()
+        .try_expand(100, &Limits::new().max_bytes(64))
+        .unwrap_err();
+    assert_eq!(error.exceeded, Exceeded::Bytes);
+    assert_eq!(error.last.len(), 8);
+}
+
+#[test]
+fn max_bytes_bounds_the_allocated_memory() {
+    for max in [10, 100, 1000, 4096, 10_000] {
+        let error = doubling()
+            .try_expand(100, &Limits::new().max_bytes(max))
+            .unwrap_err();
+        assert_eq!(error.exceeded, Exceeded::Bytes);
+        assert!(error.last.capacity() * 4 <= max, "{max}");
+        // the generation after the last one would not have fit next to it
+        assert!(error.last.len() * 3 * 4 > max, "{max}");
+    }
+}
+
+#[test]
+fn a_single_production_exceeding_the_limit_is_stopped() {
+    let system = LSystem::new(chars("AB"))
+        .with_rule(FnRule::new(chars("B"), |_: Vec<char>| vec!['B'; 1 << 20]));
+    let error = system
+        .try_expand(1, &Limits::new().max_symbols(100))
+        .unwrap_err();
+    assert_eq!((error.generation, error.last), (0, chars("AB")));
+}
+
+#[test]
+fn an_axiom_exceeding_the_limits_is_rejected() {
+    let error = doubling()
+        .try_expand(0, &Limits::new().max_symbols(0))
+        .unwrap_err();
+    assert_eq!((error.exceeded, error.generation), (Exceeded::Symbols, 0));
+}
+
+#[test]
+fn deadline() {
+    let limits = Limits::new().deadline(Instant::now());
+    let error = doubling().try_expand(1000, &limits).unwrap_err();
+    assert_eq!((error.exceeded, error.generation), (Exceeded::Deadline, 0));
+
+    // the deadline is polled while rewriting, not only between generations
+    let start = Instant::now();
+    let limits = Limits::new().timeout(Duration::from_millis(50));
+    let error = doubling().try_expand(1000, &limits).unwrap_err();
+    assert_eq!(error.exceeded, Exceeded::Deadline);
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn cancellation_from_another_thread() {
+    let token = CancellationToken::new();
+    let limits = Limits::new().cancellation(token.clone());
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        token.cancel();
+    });
+    let error = doubling().try_expand(1000, &limits).unwrap_err();
+    canceller.join().unwrap();
+    assert_eq!(error.exceeded, Exceeded::Cancelled);
+    assert_eq!(error.last.len(), 1 << error.generation);
+}
+
+#[test]
+fn free_functions_have_limited_variants() {
+    let limits = Limits::new().max_symbols(1000);
+    let rules = vec![(chars("A"), chars("AA"), 1.0)];
+    let error = try_random_lsystem(chars("A"), &rules, 64, &limits).unwrap_err();
+    assert_eq!((error.exceeded, error.generation), (Exceeded::Symbols, 9));
+    assert_eq!(
+        try_random_lsystem(chars("A"), &rules, 3, &limits),
+        Ok(chars("AAAAAAAA"))
+    );
+
+    let double = |orig: Vec<char>| orig.repeat(2);
+    let rules = [(chars("A"), double)];
+    let error = try_arbitrary_lsystem(chars("A"), &rules, 64, &limits).unwrap_err();
+    assert_eq!(error.last, doubling().expand(9));
+    let token = CancellationToken::new();
+    token.cancel();
+    let error = try_arbitrary_lsystem(chars("A"), &rules, 64, &Limits::new().cancellation(token))
+        .unwrap_err();
+    assert_eq!((error.exceeded, error.generation), (Exceeded::Cancelled, 0));
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use lsystem::cpfg;
+use lsystem::mesh::{Material, Mesh, Tubes};
+use lsystem::parametric::Module;
+use lsystem::raster::Color;
+use lsystem::turtle3d::{Segment, Turtle, Vector};
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn segment(start: Vector, end: Vector, width: f64) -> Segment {
+    Segment { start, end, width }
+}
+
+/// Checks that the mesh is closed and its faces consistently oriented: every edge is run along
+/// as often in one direction as in the other.
+fn assert_closed(mesh: &Mesh) {
+    let key = |i: usize| {
+        let p = mesh.positions[i];
+        [p.x, p.y, p.z].map(f64::to_bits)
+    };
+    let mut edges: HashMap<_, usize> = HashMap::new();
+    for face in &mesh.faces {
+        for k in 0..3 {
+            let edge = (key(face.vertices[k]), key(face.vertices[(k + 1) % 3]));
+            *edges.entry(edge).or_default() += 1;
+        }
+    }
+    for (&(a, b), &count) in &edges {
+        assert_eq!(edges.get(&(b, a)), Some(&count), "open edge");
+    }
+}
+
+fn face_normal(mesh: &Mesh, vertices: [usize; 3]) -> Vector {
+    let [a, b, c] = vertices.map(|i| mesh.positions[i]);
+    (b - a).cross(c - a)
+}
+
+fn volume(mesh: &Mesh) -> f64 {
+    mesh.faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.vertices.map(|i| mesh.positions[i]);
+            a.dot(b.cross(c)) / 6.0
+        })
+        .sum()
+}
+
+#[test]
+fn a_segment_becomes_a_closed_prism() {
+    let segments = [segment(Vector::default(), Vector::new(0.0, 3.0, 0.0), 2.0)];
+    let mesh = Tubes::new().sides(8).mesh(&segments);
+    // two rings, and two caps with a center
+    assert_eq!(mesh.positions.len(), 2 * 8 + 2 * 9);
+    assert_eq!(mesh.faces.len(), 2 * 8 + 2 * 8);
+    assert_closed(&mesh);
+    let expected = 8.0 / 2.0 * (2.0 * PI / 8.0).sin() * 3.0;
+    assert!((volume(&mesh) - expected).abs() < 1e-9, "{}", volume(&mesh));
+    for (position, normal) in mesh.positions.iter().zip(&mesh.normals).take(16) {
+        assert!((Vector::new(position.x, 0.0, position.z).length() - 1.0).abs() < 1e-9);
+        assert!((normal.length() - 1.0).abs() < 1e-9 && normal.y.abs() < 1e-9);
+    }
+}
+
+#[test]
+fn joints_share_their_cross_section() {
+    let turtle = Turtle::new(1.0, 60.0).with_width(0.2);
+    let bent = Tubes::new().sides(6).mesh(&turtle.interpret(&chars("F+F")));
+    assert_eq!(bent.faces.len(), 2 * 2 * 6 + 2 * 6);
+    assert_closed(&bent);
+    // the tilted ring at the joint keeps the tube as thick as the segments on both sides
+    let joint = Vector::new(0.0, 1.0, 0.0);
+    let outgoing = Vector::new(-(60f64).to_radians().sin(), 0.5, 0.0);
+    for &p in &bent.positions[6..12] {
+        for axis in [Vector::Y, outgoing] {
+            let offset = p - joint;
+            let distance = (offset - axis * offset.dot(axis)).length();
+            assert!((distance - 0.1).abs() < 1e-9, "{p:?}");
+        }
+    }
+
+    // turning further than a right angle starts a new tube
+    let sharp = Turtle::new(1.0, 120.0).interpret(&chars("F+F"));
+    assert_eq!(
+        Tubes::new().sides(6).mesh(&sharp).faces.len(),
+        2 * (2 * 6 + 2 * 6)
+    );
+}
+
+#[test]
+fn branches_are_tubes_of_their_own() {
+    let segments = Turtle::new(1.0, 30.0).interpret(&chars("F[+F][-F]F"));
+    let mesh = Tubes::new().sides(5).mesh(&segments);
+    assert_eq!(mesh.faces.len(), 2 * 2 * 5 + 2 * 5 + 2 * (2 * 5 + 2 * 5));
+    assert_closed(&mesh);
+    // the trunk goes straight on, without a cap at the branch point
+    let caps_at_joint = mesh
+        .positions
+        .iter()
+        .zip(&mesh.normals)
+        .filter(|(p, n)| (**p - Vector::Y).length() < 1e-9 && (n.y.abs() - 1.0).abs() < 1e-9)
+        .count();
+    assert_eq!(caps_at_joint, 0);
+}
+
+#[test]
+fn branches_are_joined_to_their_parent_by_a_collar() {
+    let trunk = |y: f64| segment(Vector::Y * y, Vector::Y * (y + 1.0), 1.0);
+    let branch = segment(Vector::Y, Vector::new(2.0, 1.0, 0.0), 0.2);
+    let mesh = Tubes::new()
+        .sides(6)
+        .mesh(&[trunk(0.0), branch, trunk(1.0)]);
+    assert_closed(&mesh);
+    // the trunk comes first, with three rings of six vertices and two caps, then the collar and
+    // the neck of the branch
+    let collar = 3 * 6 + 2 * 7;
+    let inscribed = 0.5 * (PI / 6.0).cos();
+    for i in collar..collar + 6 {
+        let (p, n) = (mesh.positions[i], mesh.normals[i]);
+        // on the trunk, within its faces, with the trunk's normals
+        let across = Vector::new(p.x, 0.0, p.z);
+        assert!((across.length() - inscribed).abs() < 1e-9, "{p:?}");
+        assert!((n - across * (1.0 / inscribed)).length() < 1e-9, "{n:?}");
+    }
+    // wider than the branch
+    assert!((collar..collar + 6).any(|i| mesh.positions[i].z.abs() > 0.1));
+    for i in collar + 6..collar + 12 {
+        let (p, n) = (mesh.positions[i], mesh.normals[i]);
+        // the neck is just outside the trunk, where the branch has its own normals
+        assert!(p.x > 0.5 && p.x < 1.0, "{p:?}");
+        assert!(
+            (Vector::new(0.0, p.y - 1.0, p.z).length() - 0.1).abs() < 1e-9,
+            "{p:?}"
+        );
+        assert!(n.x.abs() < 1e-9);
+    }
+}
+
+#[test]
+fn widths_set_the_radius() {
+    let elements = [
+        Module::new('!', vec![0.5]),
+        Module::plain('F'),
+        Module::new('!', vec![0.25]),
+        Module::plain('F'),
+    ];
+    let mesh = Tubes::new()
+        .sides(4)
+        .mesh(&Turtle::new(1.0, 90.0).interpret(&elements));
+    let radius = |i: usize| {
+        let p = mesh.positions[i];
+        Vector::new(p.x, 0.0, p.z).length()
+    };
+    // the radius at the joint is the one of the segment that starts there
+    assert!((0..4).all(|i| (radius(i) - 0.25).abs() < 1e-9));
+    assert!((4..12).all(|i| (radius(i) - 0.125).abs() < 1e-9));
+}
+
+#[test]
+fn materials_are_written_to_the_library() {
+    let segments = Turtle::new(1.0, 90.0).interpret(&chars("FFF"));
+    let leaf = Material::new("leaf", Color::rgba(0, 255, 0, 128));
+    let mesh = Tubes::new()
+        .sides(3)
+        .mesh_styled(&segments, |i, _| (i == 2).then(|| leaf.clone()));
+    assert_eq!(mesh.materials.len(), 2);
+    assert_closed(&mesh);
+
+    let obj = mesh.to_obj("plant.mtl");
+    let count = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+    assert_eq!(obj.lines().next(), Some("mtllib plant.mtl"));
+    assert_eq!(count("v "), mesh.positions.len());
+    assert_eq!(count("vn "), mesh.normals.len());
+    assert_eq!(count("f "), mesh.faces.len());
+    assert_eq!(
+        obj.lines()
+            .filter(|l| l.starts_with("usemtl"))
+            .collect::<Vec<_>>(),
+        ["usemtl branch", "usemtl leaf"]
+    );
+    for line in obj.lines().filter(|l| l.starts_with("f ")) {
+        for vertex in line.split(' ').skip(1) {
+            let (v, n) = vertex.split_once("//").unwrap();
+            assert_eq!(v, n);
+            assert!((1..=mesh.positions.len()).contains(&v.parse().unwrap()));
+        }
+    }
+    assert_eq!(
+        mesh.to_mtl(),
+        "newmtl branch\nKd 0.4 0.301961 0.2\nnewmtl leaf\nKd 0 1 0\nd 0.501961\n"
+    );
+}
+
+#[test]
+fn whitespace_in_material_names_is_replaced() {
+    let segments = Turtle::new(1.0, 90.0).interpret(&chars("F"));
+    let mesh = Tubes::new()
+        .material(Material::new("my bark\n", Color::BLACK))
+        .mesh(&segments);
+    assert!(mesh.to_obj("tree.mtl").contains("\nusemtl my_bark_\n"));
+    assert!(mesh.to_mtl().starts_with("newmtl my_bark_\n"));
+}
+
+#[test]
+#[should_panic(expected = "cannot contain whitespace")]
+fn material_libraries_cannot_contain_whitespace() {
+    let segments = Turtle::new(1.0, 90.0).interpret(&chars("F"));
+    Tubes::new().mesh(&segments).to_obj("my tree.mtl");
+}
+
+#[test]
+fn abop_monopodial_tree() {
+    let grammar = cpfg::import(include_str!("fixtures/abop_2_6.l")).unwrap();
+    let iterations = grammar.iterations.unwrap();
+    let tree = grammar.into_lsystem().expand(iterations);
+    let segments = Turtle::new(1.0, 0.0).interpret(&tree);
+    let forward_steps = tree.iter().filter(|m| m.symbol == 'F').count();
+    assert_eq!(segments.len(), forward_steps);
+    assert_eq!(segments[0].width, 10.0);
+
+    // as wide as they are, the branches start on the axis of their parent, and thinner they
+    // are joined to it by collars
+    let thin: Vec<Segment> = segments
+        .iter()
+        .map(|s| Segment {
+            width: s.width / 50.0,
+            ..*s
+        })
+        .collect();
+    for segments in [segments, thin] {
+        let mesh = Tubes::new().mesh(&segments);
+        assert_closed(&mesh);
+        for face in &mesh.faces {
+            let normal = face_normal(&mesh, face.vertices);
+            let smooth = face
+                .vertices
+                .iter()
+                .fold(Vector::default(), |sum, &i| sum + mesh.normals[i]);
+            assert!(normal.dot(smooth) > 0.0, "{face:?} faces inward");
+        }
+        assert!(volume(&mesh) > 0.0);
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "parallel")]
+
+use lsystem::context::{complex_lsystem, par_complex_lsystem, SliceRule};
+use lsystem::deterministic::{lsystem, par_lsystem};
+use lsystem::stochastic::{seeded_rng, ChanceRule};
+use lsystem::LSystem;
+use rand::Rng;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+#[test]
+fn symbol_rules_match_the_sequential_output() {
+    let rules = vec![('A', chars("AB")), ('B', chars("A"))];
+    for n in [0, 1, 10, 20, 25] {
+        assert_eq!(
+            par_lsystem(chars("A"), &rules, n),
+            lsystem(chars("A"), &rules, n)
+        );
+    }
+}
+
+#[test]
+fn slice_rules_straddling_chunks_match_the_sequential_output() {
+    // long, overlapping patterns make matches that cross chunk boundaries likely
+    let rules = vec![
+        (chars("ABAAB"), chars("C")),
+        (chars("BAB"), chars("AB")),
+        (chars("CA"), chars("ACB")),
+        (chars("A"), chars("AB")),
+        (chars("B"), chars("A")),
+    ];
+    for n in 0..24 {
+        assert_eq!(
+            par_complex_lsystem(chars("A"), &rules, n),
+            complex_lsystem(chars("A"), &rules, n),
+            "generation {n}"
+        );
+    }
+}
+
+#[test]
+fn random_grammars_match_the_sequential_output() {
+    let mut rng = seeded_rng(13);
+    for _ in 0..20 {
+        let word = |rng: &mut rand_chacha::ChaCha8Rng, len| {
+            (0..rng.gen_range(1..=len))
+                .map(|_| rng.gen_range(0..3u8))
+                .collect::<Vec<_>>()
+        };
+        let rules: Vec<(Vec<u8>, Vec<u8>)> = (0..rng.gen_range(1..6))
+            .map(|_| (word(&mut rng, 4), word(&mut rng, 3)))
+            .collect();
+        let axiom: Vec<u8> = (0..20_000).map(|_| rng.gen_range(0..3)).collect();
+        assert_eq!(
+            par_complex_lsystem(axiom.clone(), &rules, 2),
+            complex_lsystem(axiom, &rules, 2),
+            "{rules:?}"
+        );
+    }
+}
+
+#[test]
+fn lsystems_with_stochastic_rules() {
+    let certain = LSystem::new(chars("F"))
+        .with_rule(SliceRule::new(chars("FF"), chars("F")))
+        .with_rule(ChanceRule::new(chars("F"), chars("FXF"), 1.0));
+    assert_eq!(certain.par_expand(16), certain.expand(16));
+
+    let coin =
+        LSystem::new(vec!['A'; 100_000]).with_rule(ChanceRule::new(chars("A"), chars("B"), 0.5));
+    let result = coin.par_expand_with_rng(1, seeded_rng(1));
+    let heads = result.iter().filter(|c| **c == 'B').count();
+    assert!((49_000..51_000).contains(&heads));
+}
@@ -0,0 +1,118 @@
+use lsystem::branching::Branching;
+use lsystem::parametric::{parametric_lsystem, Env, Module, ParametricRule, Pattern, Template};
+use lsystem::LSystem;
+
+fn string(modules: &[Module<char>]) -> String {
+    modules.iter().map(|m| m.to_string()).collect()
+}
+
+#[test]
+fn abop_parametric_derivation() {
+    // The Algorithmic Beauty of Plants, section 1.10
+    let rules = [
+        ParametricRule::new(
+            Pattern::new('A', ["x", "y"]),
+            vec![Template::new('A')
+                .param(|env: &Env| env["x"] * 2.0)
+                .param(|env: &Env| env["x"] + env["y"])],
+        )
+        .with_condition(|env: &Env| env["y"] <= 3.0),
+        ParametricRule::new(
+            Pattern::new('A', ["x", "y"]),
+            vec![
+                Template::new('B').param(|env: &Env| env["x"]),
+                Template::new('A')
+                    .param(|env: &Env| env["x"] / env["y"])
+                    .param(0.0),
+            ],
+        )
+        .with_condition(|env: &Env| env["y"] > 3.0),
+        ParametricRule::new(Pattern::new('B', ["x"]), vec![Template::new('C')])
+            .with_condition(|env: &Env| env["x"] < 1.0),
+        ParametricRule::new(
+            Pattern::new('B', ["x"]),
+            vec![Template::new('B').param(|env: &Env| env["x"] - 1.0)],
+        )
+        .with_condition(|env: &Env| env["x"] >= 1.0),
+    ];
+    let axiom = vec![
+        Module::new('B', vec![2.0]),
+        Module::new('A', vec![4.0, 4.0]),
+    ];
+    let expected = [
+        "B(2)A(4,4)",
+        "B(1)B(4)A(1,0)",
+        "B(0)B(3)A(2,1)",
+        "CB(2)A(4,3)",
+        "CB(1)A(8,7)",
+    ];
+    for (n, expected) in expected.iter().enumerate() {
+        assert_eq!(
+            string(&parametric_lsystem(axiom.clone(), &rules, n as u32)),
+            *expected
+        );
+    }
+}
+
+#[test]
+fn context_parameters_are_bound() {
+    // A(x) < B(y) > C(z) -> B(x+y+z)
+    let rule = ParametricRule::new(
+        Pattern::new('B', ["y"]),
+        vec![Template::new('B').param(|env: &Env| env["x"] + env["y"] + env["z"])],
+    )
+    .with_left(vec![Pattern::new('A', ["x"])])
+    .with_right(vec![Pattern::new('C', ["z"])]);
+    let axiom = vec![
+        Module::new('A', vec![1.0]),
+        Module::new('B', vec![2.0]),
+        Module::new('C', vec![3.0]),
+        Module::new('B', vec![4.0]),
+        Module::new('C', vec![5.0]),
+    ];
+    let system = LSystem::new(axiom).with_rule(rule);
+    assert_eq!(string(&system.expand(1)), "A(1)B(6)C(3)B(4)C(5)");
+    let streamed: Vec<_> = system.stream(1).unwrap().collect();
+    assert_eq!(streamed, system.expand(1));
+}
+
+#[test]
+fn number_of_parameters_must_match() {
+    let rule = ParametricRule::new(Pattern::new('A', ["x"]), vec![Template::new('X')]);
+    let axiom = vec![
+        Module::plain('A'),
+        Module::new('A', vec![1.0]),
+        Module::new('A', vec![1.0, 2.0]),
+    ];
+    assert_eq!(string(&parametric_lsystem(axiom, &[rule], 1)), "AXA(1,2)");
+}
+
+#[test]
+fn context_follows_branches() {
+    // A(x) < C(y) -> C(x+y), with C's parent A across the branch [B(2)]
+    let rule = ParametricRule::new(
+        Pattern::new('C', ["y"]),
+        vec![Template::new('C').param(|env: &Env| env["x"] + env["y"])],
+    )
+    .with_left(vec![Pattern::new('A', ["x"])])
+    .with_branching(Branching::brackets());
+    let axiom = vec![
+        Module::new('A', vec![1.0]),
+        Module::plain('['),
+        Module::new('B', vec![2.0]),
+        Module::plain(']'),
+        Module::new('C', vec![3.0]),
+    ];
+    let system = LSystem::new(axiom).with_rule(rule);
+    assert_eq!(string(&system.expand(1)), "A(1)[B(2)]C(4)");
+    assert!(system.stream(1).is_none());
+}
+
+#[test]
+fn env_shadows_earlier_bindings() {
+    let mut env = Env::new();
+    env.bind("x", 1.0);
+    env.bind("x", 2.0);
+    assert_eq!(env.get("x"), Some(2.0));
+    assert_eq!(env.get("y"), None);
+}
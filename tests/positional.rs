@@ -0,0 +1,69 @@
+use lsystem::stochastic::{ChanceRule, CounterRng, WeightedRule};
+use lsystem::LSystem;
+use rand::{Rng, RngCore};
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn plant() -> LSystem<char> {
+    let alternatives = vec![
+        (chars("F[+F]F"), 1.0),
+        (chars("F[-F]"), 1.0),
+        (chars("FF"), 2.0),
+    ];
+    LSystem::new(chars("F"))
+        .with_rule(WeightedRule::new(chars("F"), alternatives).unwrap())
+        .with_rule(ChanceRule::new(chars("+"), chars("-"), 0.5))
+}
+
+#[test]
+fn seeds_reproduce_exact_outputs() {
+    let result: String = plant().expand_positional(3, 7).into_iter().collect();
+    assert_eq!(result, "F[+F]FFFFFF[-F]");
+    assert_eq!(
+        plant().expand_positional(6, 1),
+        plant().expand_positional(6, 1)
+    );
+    assert_ne!(
+        plant().expand_positional(6, 1),
+        plant().expand_positional(6, 2)
+    );
+}
+
+#[test]
+fn counter_rng_is_a_pure_function_of_its_coordinates() {
+    let mut rng = CounterRng::new(1, 2, 3);
+    let first: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+    let mut again = CounterRng::new(1, 2, 3);
+    assert_eq!(first, (0..4).map(|_| again.next_u64()).collect::<Vec<_>>());
+    assert_ne!(first[0], CounterRng::new(1, 2, 4).next_u64());
+    assert_ne!(first[0], CounterRng::new(1, 3, 3).next_u64());
+    assert_ne!(first[0], CounterRng::new(2, 2, 3).next_u64());
+    assert_ne!(first[0], CounterRng::new(3, 2, 1).next_u64());
+}
+
+#[test]
+fn counter_rngs_of_neighbouring_positions_are_independent() {
+    let n = 100_000;
+    let heads = (0..n)
+        .filter(|&position| CounterRng::new(0, 0, position).gen_bool(0.5))
+        .count();
+    assert!((49_000..51_000).contains(&heads));
+    let mean = (0..n)
+        .map(|position| CounterRng::new(5, position, 9).gen::<f64>())
+        .sum::<f64>()
+        / n as f64;
+    assert!((mean - 0.5).abs() < 0.005);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_expansion_is_identical() {
+    let system = plant();
+    for seed in 0..4 {
+        let sequential = system.expand_positional(12, seed);
+        assert!(sequential.len() > 20_000);
+        assert_eq!(system.par_expand_positional(12, seed), sequential);
+    }
+}
@@ -0,0 +1,81 @@
+use lsystem::context::complex_lsystem;
+use lsystem::deterministic::lsystem;
+use lsystem::functional::arbitrary_lsystem;
+use lsystem::stochastic::random_lsystem;
+
+const FIBONACCI: [&str; 6] = ["A", "AB", "ABA", "ABAAB", "ABAABABA", "ABAABABAABAAB"];
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+#[test]
+fn deterministic_fibonacci() {
+    let rules = vec![('A', chars("AB")), ('B', chars("A"))];
+    for (n, expected) in FIBONACCI.iter().enumerate() {
+        assert_eq!(lsystem(chars("A"), &rules, n as u32), chars(expected));
+    }
+}
+
+#[test]
+fn deterministic_first_rule_wins_and_constants_survive() {
+    let rules = vec![('A', chars("X")), ('A', chars("Y"))];
+    assert_eq!(lsystem(chars("A+A"), &rules, 1), chars("X+X"));
+}
+
+#[test]
+fn complex_matches_deterministic_for_single_element_rules() {
+    let rules = vec![(chars("A"), chars("AB")), (chars("B"), chars("A"))];
+    for (n, expected) in FIBONACCI.iter().enumerate() {
+        assert_eq!(
+            complex_lsystem(chars("A"), &rules, n as u32),
+            chars(expected)
+        );
+    }
+}
+
+#[test]
+fn complex_applies_at_most_one_rule_per_slice() {
+    let rules = vec![(chars("AB"), chars("B")), (chars("B"), chars("AB"))];
+    assert_eq!(complex_lsystem(chars("ABB"), &rules, 1), chars("BAB"));
+    assert_eq!(complex_lsystem(chars("ABB"), &rules, 2), chars("ABB"));
+}
+
+#[test]
+fn stochastic_with_certain_chances_is_deterministic() {
+    let rules = vec![
+        (chars("A"), chars("AB"), 1.0),
+        (chars("B"), chars("A"), 1.0),
+    ];
+    for (n, expected) in FIBONACCI.iter().enumerate() {
+        assert_eq!(
+            random_lsystem(chars("A"), &rules, n as u32),
+            chars(expected)
+        );
+    }
+}
+
+#[test]
+fn stochastic_picks_one_of_the_alternatives() {
+    let rules = vec![(chars("A"), chars("B"), 0.5), (chars("A"), chars("C"), 1.0)];
+    for _ in 0..100 {
+        let result = random_lsystem(chars("AAAA"), &rules, 1);
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().all(|c| *c == 'B' || *c == 'C'));
+    }
+}
+
+#[test]
+fn arbitrary_applies_the_transformation() {
+    let rules = vec![(chars("AB"), |orig: Vec<char>| {
+        orig.into_iter().rev().collect()
+    })];
+    assert_eq!(arbitrary_lsystem(chars("ABAB"), &rules, 1), chars("BABA"));
+    assert_eq!(arbitrary_lsystem(chars("ABAB"), &rules, 2), chars("BBAA"));
+}
+
+#[test]
+fn many_generations_do_not_grow_the_stack() {
+    let rules = vec![('A', chars("B")), ('B', chars("C")), ('C', chars("A"))];
+    assert_eq!(lsystem(chars("ABC"), &rules, 300_001), chars("BCA"));
+}
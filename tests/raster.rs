@@ -0,0 +1,212 @@
+use lsystem::deterministic::SymbolRule;
+use lsystem::raster::{Color, Image, Pen, Raster};
+use lsystem::turtle::{Point, Segment, Turtle};
+use lsystem::LSystem;
+
+fn horizontal(y: f64) -> Segment {
+    Segment {
+        start: Point::new(0.0, y),
+        end: Point::new(10.0, y),
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Splits a PNG file into its chunks, checking their checksums.
+fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut chunks = Vec::new();
+    let mut rest = &png[8..];
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+        let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+        assert_eq!(crc, crc32(&rest[4..8 + len]), "checksum of {kind:?}");
+        chunks.push((kind.try_into().unwrap(), data.to_vec()));
+        rest = &rest[12 + len..];
+    }
+    chunks
+}
+
+/// Decodes a zlib stream made of stored deflate blocks, checking its checksum.
+fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+    assert_eq!(((zlib[0] as u32) << 8 | zlib[1] as u32) % 31, 0);
+    let mut data = Vec::new();
+    let mut at = 2;
+    loop {
+        let last = zlib[at] & 1 == 1;
+        assert_eq!(zlib[at] >> 1, 0, "only stored blocks");
+        let len = u16::from_le_bytes([zlib[at + 1], zlib[at + 2]]);
+        let nlen = u16::from_le_bytes([zlib[at + 3], zlib[at + 4]]);
+        assert_eq!(len, !nlen);
+        data.extend_from_slice(&zlib[at + 5..at + 5 + len as usize]);
+        at += 5 + len as usize;
+        if last {
+            break;
+        }
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in &data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    assert_eq!(&zlib[at..], &((b << 16) | a).to_be_bytes());
+    data
+}
+
+fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    let chunks = chunks(png);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+    let (first, last) = (kinds[0], kinds[kinds.len() - 1]);
+    assert_eq!((first, last), (b"IHDR", b"IEND"));
+    assert!(kinds.len() > 2 && kinds[1..kinds.len() - 1].iter().all(|&k| k == b"IDAT"));
+    let header = &chunks[0].1;
+    let width = u32::from_be_bytes(header[..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    assert_eq!(&header[8..], &[8, 6, 0, 0, 0]);
+    let zlib: Vec<u8> = chunks[1..chunks.len() - 1]
+        .iter()
+        .flat_map(|(_, data)| data.iter().copied())
+        .collect();
+    let scanlines = inflate_stored(&zlib);
+    let row = width as usize * 4;
+    assert_eq!(scanlines.len(), (row + 1) * height as usize);
+    let mut rgba = Vec::new();
+    for line in scanlines.chunks(row + 1) {
+        assert_eq!(line[0], 0, "no filter");
+        rgba.extend_from_slice(&line[1..]);
+    }
+    (width, height, rgba)
+}
+
+#[test]
+fn png_round_trips() {
+    let image = Raster::new(7, 5).render(&[horizontal(0.0), horizontal(1.0)]);
+    let (width, height, rgba) = decode(&image.to_png());
+    assert_eq!((width, height), (7, 5));
+    assert_eq!(rgba, image.as_rgba());
+}
+
+#[test]
+fn large_png_spans_several_blocks() {
+    let koch =
+        LSystem::new(vec!['F']).with_rule(SymbolRule::new('F', "F+F-F-F+F".chars().collect()));
+    let segments = Turtle::new(1.0, 90.0)
+        .with_heading(0.0)
+        .interpret(&koch.expand(4));
+    let image = Raster::new(300, 120).render(&segments);
+    assert!(image.as_rgba().len() > 2 * 65535);
+    let (_, _, rgba) = decode(&image.to_png());
+    assert_eq!(rgba, image.as_rgba());
+}
+
+#[test]
+fn image_data_is_split_into_several_chunks() {
+    let image = Raster::new(800, 400).render(&[horizontal(0.0), horizontal(1.0)]);
+    let png = image.to_png();
+    let idat = chunks(&png)
+        .into_iter()
+        .filter(|(kind, _)| kind == b"IDAT")
+        .count();
+    assert_eq!(idat, 2);
+    let (_, _, rgba) = decode(&png);
+    assert_eq!(rgba, image.as_rgba());
+}
+
+#[test]
+fn lines_are_anti_aliased() {
+    // one unit per pixel: the lines at 0.5 and 10.5 run through the middle of the first and last
+    // row, the one at 5.5 through the middle of row 5, and the one at 3 between rows 7 and 8
+    let segments = [0.5, 10.5, 5.5, 3.0].map(horizontal);
+    let image = Raster::new(10, 11).margin(0).render(&segments);
+    assert_eq!(image.pixel(5, 0), Color::BLACK);
+    assert_eq!(image.pixel(5, 5), Color::BLACK);
+    assert_eq!(image.pixel(5, 10), Color::BLACK);
+    let half = image.pixel(5, 7);
+    assert!(half.r > 100 && half.r < 155, "{half:?}");
+    assert_eq!(image.pixel(5, 8), half);
+    assert_eq!(image.pixel(5, 3), Color::WHITE);
+}
+
+#[test]
+fn wide_pens_cover_more_pixels() {
+    let count_dark = |image: &Image| {
+        (0..image.height())
+            .flat_map(|y| (0..image.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.pixel(x, y).r < 128)
+            .count()
+    };
+    let segments = [horizontal(0.5), horizontal(10.5)];
+    let thin = Raster::new(40, 40).render(&segments);
+    let wide = Raster::new(40, 40)
+        .pen(Pen::new(Color::BLACK, 5.0))
+        .render(&segments);
+    assert!(count_dark(&wide) > 4 * count_dark(&thin));
+    let styled = Raster::new(10, 11)
+        .margin(0)
+        .render_styled(&segments, |i, _| {
+            (i == 1).then_some(Pen::new(Color::rgb(255, 0, 0), 1.0))
+        });
+    assert_eq!(styled.pixel(5, 0), Color::rgb(255, 0, 0));
+    assert_eq!(styled.pixel(5, 10), Color::BLACK);
+}
+
+#[test]
+fn drawing_is_fitted_within_the_margins() {
+    let square: Vec<char> = "F+F+F+F".chars().collect();
+    let image = Raster::new(100, 60)
+        .margin(10)
+        .background(Color::TRANSPARENT)
+        .render(&Turtle::new(3.0, 90.0).interpret(&square));
+    let touched: Vec<(u32, u32)> = (0..60)
+        .flat_map(|y| (0..100).map(move |x| (x, y)))
+        .filter(|&(x, y)| image.pixel(x, y).a > 0)
+        .collect();
+    // a 40 pixel square in the middle of the 80 by 40 pixels within the margins
+    let xs = touched.iter().map(|&(x, _)| x);
+    let ys = touched.iter().map(|&(_, y)| y);
+    assert_eq!((xs.clone().min(), xs.max()), (Some(29), Some(70)));
+    assert_eq!((ys.clone().min(), ys.max()), (Some(9), Some(50)));
+}
+
+#[test]
+fn margins_wider_than_the_image_leave_the_background() {
+    let image = Raster::new(4, 3)
+        .margin(u32::MAX)
+        .render(&[horizontal(0.0), horizontal(1.0)]);
+    assert!(image.as_rgba().iter().all(|&c| c == 255));
+}
+
+#[test]
+#[should_panic(expected = "at least one pixel")]
+fn rasters_cannot_be_empty() {
+    Raster::new(3, 0);
+}
+
+#[test]
+#[should_panic(expected = "at least one pixel")]
+fn images_cannot_be_empty() {
+    Image::new(0, 0, Color::WHITE);
+}
+
+#[test]
+fn ppm_drops_alpha() {
+    let image = Raster::new(3, 2).render(&[]);
+    let ppm = image.to_ppm();
+    assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+    assert_eq!(ppm.len(), b"P6\n3 2\n255\n".len() + 3 * 2 * 3);
+    assert!(ppm[11..].iter().all(|&c| c == 255));
+}
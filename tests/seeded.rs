@@ -0,0 +1,73 @@
+use lsystem::stochastic::{random_lsystem_with_rng, seeded_rng, ChanceRule};
+use lsystem::LSystem;
+use rand::RngCore;
+
+fn string(elements: Vec<char>) -> String {
+    elements.into_iter().collect()
+}
+
+fn plant() -> LSystem<char> {
+    LSystem::new(vec!['F'])
+        .with_rule(ChanceRule::new(vec!['F'], "F[+F]F".chars().collect(), 0.5))
+        .with_rule(ChanceRule::new(vec!['F'], "F[-F]".chars().collect(), 1.0))
+}
+
+#[test]
+fn seeds_reproduce_exact_outputs() {
+    let rules = vec![
+        (vec!['A'], vec!['A', 'B'], 0.5),
+        (vec!['B'], vec!['A'], 0.75),
+    ];
+    let expected = [(0, "AAAA"), (1, "AAABAABABA"), (42, "ABAAAAABAA")];
+    for (seed, expected) in expected {
+        let result = random_lsystem_with_rng(vec!['A'], &rules, 8, seeded_rng(seed));
+        assert_eq!(string(result), expected, "seed {seed}");
+    }
+    assert_eq!(
+        string(plant().expand_seeded(3, 2024)),
+        "F[+F]F[-F[-F]][-F[-F][-F[+F]F]]"
+    );
+}
+
+#[test]
+fn generations_are_reproducible() {
+    let generations: Vec<String> = plant()
+        .generations_with_rng(seeded_rng(5))
+        .take(4)
+        .map(string)
+        .collect();
+    assert_eq!(
+        generations,
+        [
+            "F",
+            "F[+F]F",
+            "F[+F]F[+F[+F]F]F[-F]",
+            "F[-F][+F[-F]]F[+F]F[+F[+F]F[+F[+F]F]F[+F]F]F[-F][-F[-F]]"
+        ]
+    );
+    assert_eq!(
+        string(plant().expand_with_rng(3, seeded_rng(5))),
+        generations[3]
+    );
+}
+
+#[test]
+fn streams_are_reproducible() {
+    let plant = plant();
+    let stream = plant.stream_with_rng(3, seeded_rng(5)).unwrap();
+    assert_eq!(
+        stream.collect::<String>(),
+        "F[+F]F[+F[-F]]F[-F][+F[+F]F[-F[+F]F]]F[+F]F[+F[-F]]F[-F]"
+    );
+}
+
+#[test]
+fn any_rng_can_be_borrowed() {
+    let mut rng = seeded_rng(9);
+    let first = plant().expand_with_rng(4, &mut rng);
+    let second = plant().expand_with_rng(4, &mut rng);
+    let mut fresh = seeded_rng(9);
+    assert_eq!(first, plant().expand_with_rng(4, &mut fresh));
+    assert_eq!(second, plant().expand_with_rng(4, &mut fresh));
+    assert_eq!(rng.next_u64(), fresh.next_u64());
+}
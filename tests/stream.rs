@@ -0,0 +1,75 @@
+use std::ops::Range;
+
+use lsystem::context::SliceRule;
+use lsystem::deterministic::SymbolRule;
+use lsystem::functional::FnRule;
+use lsystem::stochastic::ChanceRule;
+use lsystem::{LSystem, Rule};
+use rand::RngCore;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn assert_streams_like_expand(system: &LSystem<char>, generations: u32) {
+    for n in 0..generations {
+        let streamed: Vec<char> = system.stream(n).unwrap().collect();
+        assert_eq!(streamed, system.expand(n), "generation {n}");
+    }
+}
+
+#[test]
+fn streams_context_free_grammars() {
+    let koch = LSystem::new(chars("F-F-F-F")).with_rule(SymbolRule::new('F', chars("F+F-F-F+F")));
+    assert_streams_like_expand(&koch, 5);
+
+    let fibonacci = LSystem::new(chars("A"))
+        .with_rule(SymbolRule::new('A', chars("AB")))
+        .with_rule(SymbolRule::new('B', chars("A")));
+    assert_streams_like_expand(&fibonacci, 20);
+}
+
+#[test]
+fn streams_rules_matching_slices() {
+    let system = LSystem::new(chars("ABBA"))
+        .with_rule(SliceRule::new(chars("ABB"), chars("B")))
+        .with_rule(SliceRule::new(chars("BA"), chars("AAB")))
+        .with_rule(SymbolRule::new('A', chars("AB")))
+        .with_rule(FnRule::new(chars("BB"), |bb: Vec<char>| bb.repeat(2)));
+    assert_streams_like_expand(&system, 10);
+}
+
+#[test]
+fn rules_that_vanish_or_never_fire() {
+    let system = LSystem::new(chars("AXAXA"))
+        .with_rule(SymbolRule::new('X', vec![]))
+        .with_rule(ChanceRule::new(chars("A"), chars("never"), 0.0));
+    assert_streams_like_expand(&system, 4);
+
+    let empty = LSystem::new(vec![]).with_rule(SymbolRule::new('A', chars("AA")));
+    assert_streams_like_expand(&empty, 4);
+}
+
+#[test]
+fn streams_huge_generations_lazily() {
+    let koch = LSystem::new(chars("F")).with_rule(SymbolRule::new('F', chars("F+F-F-F+F")));
+    // generation 40 has more than 10^28 elements, but its prefix is available right away
+    let prefix: String = koch.stream(40).unwrap().take(12).collect();
+    assert_eq!(prefix, "F+F-F-F+F+F+");
+}
+
+struct Anywhere;
+
+impl Rule<char> for Anywhere {
+    fn matches(&self, _: &[char], _: usize, _: &mut dyn RngCore) -> Option<usize> {
+        None
+    }
+
+    fn produce(&self, _: &[char], _: Range<usize>, _: &mut dyn RngCore, _: &mut Vec<char>) {}
+}
+
+#[test]
+fn rules_without_span_cannot_be_streamed() {
+    let system = LSystem::new(chars("A")).with_rule(Anywhere);
+    assert!(system.stream(3).is_none());
+}
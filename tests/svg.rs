@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use lsystem::deterministic::SymbolRule;
+use lsystem::svg::{Stroke, Svg};
+use lsystem::turtle::{Command, Point, Segment, Turtle};
+use lsystem::LSystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+/// Compares `actual` with the golden file `tests/fixtures/<name>`, or overwrites the golden file
+/// when `UPDATE_GOLDEN` is set.
+fn assert_golden(name: &str, actual: &str) {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert!(actual == expected, "{name} differs from its golden file");
+}
+
+#[test]
+fn koch_curve() {
+    let koch = LSystem::new(chars("F")).with_rule(SymbolRule::new('F', chars("F+F-F-F+F")));
+    let segments = Turtle::new(10.0, 90.0)
+        .with_heading(0.0)
+        .interpret(&koch.expand(3));
+    let image = Svg::new().render(&segments);
+    // a single stroke without branches is a single polyline
+    assert_eq!(image.matches("<polyline").count(), 1);
+    assert_golden("koch.svg", &image);
+}
+
+#[test]
+fn sierpinski_triangle() {
+    let sierpinski = LSystem::new(chars("F-G-G"))
+        .with_rule(SymbolRule::new('F', chars("F-G+F+G-F")))
+        .with_rule(SymbolRule::new('G', chars("GG")));
+    let commands = HashMap::from([
+        ('F', Command::Forward(None)),
+        ('G', Command::Forward(None)),
+        // turning left on `-` draws the triangle above the x axis
+        ('+', Command::Right(None)),
+        ('-', Command::Left(None)),
+    ]);
+    let segments = Turtle::new(10.0, 120.0)
+        .with_heading(0.0)
+        .interpret_with(&sierpinski.expand(4), |c| commands.get(c).copied());
+    let image = Svg::new()
+        .stroke(Stroke::new("navy", 2.0))
+        .background("white")
+        .width(400.0)
+        .render(&segments);
+    assert_golden("sierpinski.svg", &image);
+}
+
+#[test]
+fn dragon_curve() {
+    let dragon = LSystem::new(chars("FX"))
+        .with_rule(SymbolRule::new('X', chars("X+YF+")))
+        .with_rule(SymbolRule::new('Y', chars("-FX-Y")));
+    let segments = Turtle::new(5.0, 90.0).interpret(&dragon.expand(10));
+    assert_eq!(segments.len(), 1024);
+    let image = Svg::new().render_styled(&segments, |i, _| {
+        (i >= segments.len() / 2).then(|| Stroke::new("crimson", 1.0))
+    });
+    assert_eq!(image.matches("<polyline").count(), 2);
+    assert_golden("dragon.svg", &image);
+}
+
+#[test]
+fn branches_start_new_polylines() {
+    let segments = Turtle::new(1.0, 90.0).interpret(&chars("F[+F]F"));
+    let image = Svg::new().render(&segments);
+    assert_eq!(image.matches("<polyline").count(), 2);
+    assert!(image.contains(r#"points="0,0 0,-1 -1,-1""#));
+    assert!(image.contains(r#"points="0,-1 0,-2""#));
+}
+
+#[test]
+fn view_box_fits_the_drawing() {
+    let segments = [Segment {
+        start: Point::new(-2.0, 1.0),
+        end: Point::new(4.0, 3.0),
+    }];
+    let image = Svg::new()
+        .stroke(Stroke::new("black", 2.0))
+        .margin(0.0)
+        .width(100.0)
+        .render(&segments);
+    assert!(image.contains(r#"viewBox="-3 -4 8 4" width="100" height="50""#));
+    let empty = Svg::new().render(&[]);
+    assert!(empty.contains(r#"viewBox="-1 -1 2 2""#));
+    assert!(!empty.contains("<polyline"));
+}
+
+#[test]
+fn colors_are_escaped() {
+    let segments = Turtle::new(1.0, 90.0).interpret(&chars("F"));
+    let image = Svg::new()
+        .stroke(Stroke::new("a\"b", 1.0))
+        .background("<red> & 'blue'")
+        .render(&segments);
+    assert!(image.contains(r#"stroke="a&quot;b""#));
+    assert!(image.contains(r#"fill="&lt;red&gt; &amp; &apos;blue&apos;""#));
+}
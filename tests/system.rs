@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+use lsystem::context::SliceRule;
+use lsystem::deterministic::{lsystem, SymbolRule};
+use lsystem::functional::FnRule;
+use lsystem::stochastic::ChanceRule;
+use lsystem::{LSystem, Rule};
+use rand::RngCore;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+#[test]
+fn symbol_rules_behave_like_lsystem() {
+    let rules = vec![('A', chars("AB")), ('B', chars("A"))];
+    let mut system = LSystem::new(chars("A"));
+    for rule in rules.clone() {
+        system.push_rule(SymbolRule::from(rule));
+    }
+    for n in 0..10 {
+        assert_eq!(system.expand(n), lsystem(chars("A"), &rules, n));
+    }
+}
+
+#[test]
+fn mixed_rules_apply_in_order() {
+    let system = LSystem::new(chars("ABCD"))
+        .with_rule(SliceRule::new(chars("BC"), chars("x")))
+        .with_rule(SymbolRule::new('B', chars("never")))
+        .with_rule(ChanceRule::new(chars("A"), chars("a"), 1.0))
+        .with_rule(FnRule::new(chars("D"), |d: Vec<char>| d.repeat(3)));
+    assert_eq!(system.expand(1), chars("axDDD"));
+    assert_eq!(system.axiom(), chars("ABCD"));
+    assert_eq!(system.rules().len(), 4);
+}
+
+#[test]
+fn rules_that_never_fire_leave_constants() {
+    let system = LSystem::new(chars("A+B")).with_rule(ChanceRule::new(chars("A"), chars("B"), 0.0));
+    assert_eq!(system.expand(5), chars("A+B"));
+}
+
+/// Swaps a pair of elements, but only when it is at the very start of a generation.
+struct SwapAtStart;
+
+impl Rule<char> for SwapAtStart {
+    fn matches(&self, input: &[char], at: usize, _: &mut dyn RngCore) -> Option<usize> {
+        (at == 0 && input.len() >= 2).then_some(2)
+    }
+
+    fn produce(
+        &self,
+        input: &[char],
+        matched: Range<usize>,
+        _: &mut dyn RngCore,
+        out: &mut Vec<char>,
+    ) {
+        out.extend(input[matched].iter().rev());
+    }
+}
+
+#[test]
+fn custom_rules_can_inspect_their_position() {
+    let system = LSystem::new(chars("ABAB")).with_rule(SwapAtStart);
+    assert_eq!(system.expand(1), chars("BAAB"));
+    assert_eq!(system.expand(2), chars("ABAB"));
+}
@@ -0,0 +1,104 @@
+use lsystem::deterministic::SymbolRule;
+use lsystem::parametric::Module;
+use lsystem::turtle::{Command, Point, Segment, Turtle};
+use lsystem::LSystem;
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn close(a: Point, b: Point) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
+
+/// The coordinates of the start and the end of a segment.
+type Line = ((f64, f64), (f64, f64));
+
+fn assert_segments(actual: &[Segment], expected: &[Line]) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?}");
+    for (segment, &((x0, y0), (x1, y1))) in actual.iter().zip(expected) {
+        assert!(
+            close(segment.start, Point::new(x0, y0)) && close(segment.end, Point::new(x1, y1)),
+            "{segment:?} is not {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn draws_and_moves() {
+    let turtle = Turtle::new(2.0, 90.0);
+    assert_segments(
+        &turtle.interpret(&chars("F-fF")),
+        &[((0.0, 0.0), (0.0, 2.0)), ((2.0, 2.0), (4.0, 2.0))],
+    );
+    assert_segments(
+        &turtle.interpret(&chars("F|F")),
+        &[((0.0, 0.0), (0.0, 2.0)), ((0.0, 2.0), (0.0, 0.0))],
+    );
+}
+
+#[test]
+fn branches_restore_the_state() {
+    let turtle = Turtle::new(1.0, 90.0);
+    assert_segments(
+        &turtle.interpret(&chars("F[+F]F]")),
+        &[
+            ((0.0, 0.0), (0.0, 1.0)),
+            ((0.0, 1.0), (-1.0, 1.0)),
+            ((0.0, 1.0), (0.0, 2.0)),
+        ],
+    );
+}
+
+#[test]
+fn origin_heading_and_ignored_symbols() {
+    let turtle = Turtle::new(1.0, 45.0)
+        .with_origin(Point::new(5.0, 5.0))
+        .with_heading(0.0);
+    assert_segments(
+        &turtle.interpret(&chars("XFY")),
+        &[((5.0, 5.0), (6.0, 5.0))],
+    );
+}
+
+#[test]
+fn module_parameters_override_step_and_angle() {
+    let modules = vec![
+        Module::new('F', vec![3.0]),
+        Module::new('+', vec![90.0]),
+        Module::plain('F'),
+        Module::new('A', vec![1.0]),
+    ];
+    assert_segments(
+        &Turtle::new(1.0, 10.0).interpret(&modules),
+        &[((0.0, 0.0), (0.0, 3.0)), ((0.0, 3.0), (-1.0, 3.0))],
+    );
+}
+
+#[test]
+fn custom_mapping() {
+    #[derive(PartialEq, Clone)]
+    enum Plant {
+        Stem,
+        Leaf,
+    }
+    let elements = [Plant::Stem, Plant::Leaf, Plant::Stem];
+    let segments = Turtle::new(1.0, 90.0).interpret_with(&elements, |e| match e {
+        Plant::Stem => Some(Command::Forward(None)),
+        Plant::Leaf => Some(Command::Right(None)),
+    });
+    assert_segments(
+        &segments,
+        &[((0.0, 0.0), (0.0, 1.0)), ((0.0, 1.0), (1.0, 1.0))],
+    );
+}
+
+#[test]
+fn koch_curve_spans_its_width() {
+    let koch = LSystem::new(chars("F")).with_rule(SymbolRule::new('F', chars("F+F-F-F+F")));
+    let segments = Turtle::new(1.0, 90.0)
+        .with_heading(0.0)
+        .interpret(&koch.expand(3));
+    assert_eq!(segments.len(), 125);
+    assert!(close(segments.last().unwrap().end, Point::new(27.0, 0.0)));
+}
@@ -0,0 +1,144 @@
+use lsystem::deterministic::SymbolRule;
+use lsystem::parametric::Module;
+use lsystem::turtle3d::{Segment, Tropism, Turtle, Vector};
+use lsystem::{turtle, LSystem};
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+fn assert_close(actual: Vector, expected: Vector) {
+    assert!(
+        (actual - expected).length() < 1e-9,
+        "{actual:?} is not {expected:?}"
+    );
+}
+
+fn direction(segment: &Segment) -> Vector {
+    (segment.end - segment.start).normalized().unwrap()
+}
+
+#[test]
+fn turns_in_the_plane_match_the_2d_turtle() {
+    let plant = LSystem::new(vec!['X'])
+        .with_rule(SymbolRule::new('X', chars("F[+X][-X]FX")))
+        .with_rule(SymbolRule::new('F', chars("FF")));
+    let generation = plant.expand(4);
+    let flat = turtle::Turtle::new(1.0, 25.7).interpret(&generation);
+    let spatial = Turtle::new(1.0, 25.7).interpret(&generation);
+    assert_eq!(flat.len(), spatial.len());
+    for (a, b) in flat.iter().zip(&spatial) {
+        assert_close(b.start, Vector::new(a.start.x, a.start.y, 0.0));
+        assert_close(b.end, Vector::new(a.end.x, a.end.y, 0.0));
+    }
+}
+
+#[test]
+fn pitches_and_rolls() {
+    let turtle = Turtle::new(1.0, 90.0);
+    let heading = |s: &str| direction(turtle.interpret(&chars(s)).last().unwrap());
+    assert_close(heading("&F"), -Vector::Z);
+    assert_close(heading("^F"), Vector::Z);
+    assert_close(heading("\\F"), Vector::Y);
+    // rolling tilts the back of the turtle, which pitching down then turns away from
+    assert_close(heading("\\&F"), Vector::X);
+    assert_close(heading("/&F"), -Vector::X);
+    assert_close(heading("/+F"), Vector::Z);
+    assert_close(heading("|F"), -Vector::Y);
+}
+
+#[test]
+fn branches_restore_position_and_orientation() {
+    let segments = Turtle::new(1.0, 45.0).interpret(&chars("]F[&/F]F"));
+    assert_eq!(segments.len(), 3);
+    assert_close(segments[2].start, Vector::Y);
+    assert_close(segments[2].end, Vector::Y * 2.0);
+}
+
+#[test]
+fn parameters_override_step_and_angle() {
+    let elements = [
+        Module::new('F', vec![2.0]),
+        Module::new('&', vec![90.0]),
+        Module::plain('F'),
+    ];
+    let segments = Turtle::new(1.0, 10.0).interpret(&elements);
+    assert_close(segments[0].end, Vector::new(0.0, 2.0, 0.0));
+    assert_close(segments[1].end, Vector::new(0.0, 2.0, -1.0));
+}
+
+#[test]
+fn roll_to_horizontal_levels_the_left_direction() {
+    let module = |symbol, angle| Module::new(symbol, vec![angle]);
+    let mut elements = vec![
+        module('+', 30.0),
+        module('&', 40.0),
+        module('\\', 70.0),
+        Module::plain('$'),
+    ];
+    // draw the heading, the up direction and the left direction
+    for turn in ["", "^", "+"] {
+        elements.push(Module::plain('['));
+        if let Some(symbol) = turn.chars().next() {
+            elements.push(module(symbol, 90.0));
+        }
+        elements.push(Module::plain('F'));
+        elements.push(Module::plain(']'));
+    }
+    let segments = Turtle::new(1.0, 0.0).interpret(&elements);
+    let [heading, up, left] = [0, 1, 2].map(|i| direction(&segments[i]));
+    assert!(left.y.abs() < 1e-9, "{left:?}");
+    assert!(heading.dot(left).abs() < 1e-9 && up.dot(left).abs() < 1e-9);
+    assert!(up.y > 0.0);
+    assert_close(heading.cross(left), up);
+}
+
+#[test]
+fn tropism_bends_toward_its_direction() {
+    let gravity = Tropism::new(-Vector::Y, 0.5);
+    let turtle = Turtle::new(1.0, 90.0).with_tropism(gravity);
+    let segments = turtle.interpret(&chars("+FFFFFFFFFFFFFFFFFFFF"));
+    assert_close(direction(&segments[0]), -Vector::X);
+    assert_close(
+        direction(&segments[1]),
+        Vector::new(-(0.5f64).cos(), -(0.5f64).sin(), 0.0),
+    );
+    let downward: Vec<f64> = segments.iter().map(|s| -direction(s).y).collect();
+    assert!(downward.windows(2).all(|w| w[0] < w[1]), "{downward:?}");
+    assert!(downward[19] > 0.999 && downward[19] <= 1.0);
+
+    // growing along the tropism, there is nothing to bend
+    let straight = turtle.interpret(&chars("|FFF"));
+    assert_close(straight[2].end, Vector::Y * -3.0);
+}
+
+#[test]
+fn orientation_sets_the_frame() {
+    let turtle = Turtle::new(1.0, 90.0)
+        .with_origin(Vector::new(1.0, 1.0, 1.0))
+        .with_orientation(Vector::X * 3.0, Vector::new(1.0, 1.0, 0.0));
+    let segments = turtle.interpret(&chars("F+F"));
+    assert_close(segments[0].end, Vector::new(2.0, 1.0, 1.0));
+    assert_close(segments[1].end, Vector::new(2.0, 1.0, 0.0));
+}
+
+#[test]
+fn widths_are_set_decreased_and_restored() {
+    let elements = [
+        Module::plain('F'),
+        Module::new('!', vec![3.0]),
+        Module::plain('['),
+        Module::plain('!'),
+        Module::plain('F'),
+        Module::plain(']'),
+        Module::plain('F'),
+    ];
+    let mut turtle = Turtle::new(1.0, 90.0).with_width(2.0);
+    turtle.width_decrement = 0.5;
+    let widths: Vec<f64> = turtle
+        .interpret(&elements)
+        .iter()
+        .map(|s| s.width)
+        .collect();
+    assert_eq!(widths, [2.0, 2.5, 3.0]);
+}
@@ -0,0 +1,94 @@
+use lsystem::stochastic::{WeightError, WeightedRule};
+use lsystem::LSystem;
+
+fn alternatives(weights: &[f64]) -> Vec<(Vec<char>, f64)> {
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (vec![(b'a' + i as u8) as char], *w))
+        .collect()
+}
+
+/// How often each alternative was chosen when rewriting `n` elements once.
+fn frequencies(weights: &[f64], n: usize, seed: u64) -> Vec<f64> {
+    let rule = WeightedRule::new(vec!['X'], alternatives(weights)).unwrap();
+    let result = LSystem::new(vec!['X'; n])
+        .with_rule(rule)
+        .expand_seeded(1, seed);
+    assert_eq!(result.len(), n);
+    (0..weights.len())
+        .map(|i| {
+            let alternative = (b'a' + i as u8) as char;
+            result.iter().filter(|c| **c == alternative).count() as f64 / n as f64
+        })
+        .collect()
+}
+
+#[test]
+fn weights_are_normalised() {
+    let rule = WeightedRule::new(vec!['X'], alternatives(&[1.0, 0.0, 3.0])).unwrap();
+    let probabilities: Vec<f64> = rule.alternatives().map(|(_, p)| p).collect();
+    assert_eq!(probabilities, vec![0.25, 0.0, 0.75]);
+    assert_eq!(rule.original(), ['X']);
+}
+
+#[test]
+fn realised_frequencies_match_the_weights() {
+    let n = 200_000;
+    // the standard error of each frequency is below 0.0012, so this is more than six sigma
+    let tolerance = 0.008;
+    for (weights, seed) in [
+        (vec![1.0, 1.0], 1),
+        (vec![1.0, 2.0, 7.0], 2),
+        (vec![0.05, 0.0, 0.95], 3),
+        (vec![3.0; 6], 4),
+    ] {
+        let total: f64 = weights.iter().sum();
+        for (freq, weight) in frequencies(&weights, n, seed).into_iter().zip(&weights) {
+            let expected = weight / total;
+            assert!(
+                (freq - expected).abs() < tolerance,
+                "{weights:?}: {freq} instead of {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn zero_weights_are_never_chosen() {
+    let frequencies = frequencies(&[0.0, 1.0, 0.0], 10_000, 5);
+    assert_eq!(frequencies, vec![0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn invalid_weights_are_rejected() {
+    let invalid =
+        |weights: &[f64]| WeightedRule::new(vec!['X'], alternatives(weights)).unwrap_err();
+    assert_eq!(invalid(&[]), WeightError::Empty);
+    assert_eq!(invalid(&[1.0, -0.5]), WeightError::Negative { index: 1 });
+    assert_eq!(invalid(&[0.0, 0.0]), WeightError::AllZero);
+    assert_eq!(invalid(&[f64::NAN]), WeightError::NotFinite { index: 0 });
+    assert_eq!(
+        invalid(&[1.0, f64::INFINITY]),
+        WeightError::NotFinite { index: 1 }
+    );
+    assert_eq!(invalid(&[f64::MAX, f64::MAX]), WeightError::TotalNotFinite);
+    assert_eq!(
+        invalid(&[2.0, -1.0]).to_string(),
+        "the weight of alternative 1 is negative"
+    );
+}
+
+#[test]
+fn only_matching_slices_are_replaced() {
+    let rule = WeightedRule::new(
+        vec!['A', 'B'],
+        vec![(vec!['x'], 1.0), (vec!['y', 'y'], 1.0)],
+    )
+    .unwrap();
+    let result = LSystem::new("ABAAB".chars().collect())
+        .with_rule(rule)
+        .expand_seeded(1, 0);
+    let result: String = result.into_iter().collect();
+    assert!(["xAx", "xAyy", "yyAx", "yyAyy"].contains(&result.as_str()));
+}